@@ -0,0 +1,322 @@
+use std::fmt;
+use std::path::PathBuf;
+
+/// Where to read the input from. `-` stands for stdin.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Input {
+    Stdin,
+    File(PathBuf),
+}
+
+impl Input {
+    fn from_arg(arg: &str) -> Self {
+        if arg == "-" {
+            Input::Stdin
+        } else {
+            Input::File(PathBuf::from(arg))
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Help,
+    Transform {
+        input: Input,
+        root_attributes: Vec<String>,
+        all_attributes: Vec<String>,
+        check_end_names: bool,
+        watch_on_attribute: Option<String>,
+        json: bool,
+    },
+    ScopeCss {
+        input: Input,
+        scope_attribute: String,
+    },
+    MinifyCss {
+        input: Input,
+    },
+    JsDeps {
+        input: Input,
+    },
+    Assets {
+        inputs: Vec<Input>,
+    },
+    Check {
+        paths: Vec<PathBuf>,
+        check_end_names: bool,
+    },
+}
+
+/// Error raised when the command line arguments are invalid
+#[derive(Debug, Clone, PartialEq)]
+pub struct UsageError(pub String);
+
+impl fmt::Display for UsageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for UsageError {}
+
+fn usage_error(message: impl Into<String>) -> UsageError {
+    UsageError(message.into())
+}
+
+/// Splits the arguments into options and positional arguments.
+/// Options can be given as `--name value` or `--name=value`.
+struct ArgReader<'a> {
+    args: &'a [String],
+    pos: usize,
+}
+
+impl<'a> ArgReader<'a> {
+    fn new(args: &'a [String]) -> Self {
+        ArgReader { args, pos: 0 }
+    }
+
+    /// Return the next argument, split into the option name and the inline value if it's an option
+    fn next(&mut self) -> Option<(&'a str, Option<&'a str>)> {
+        let arg = self.args.get(self.pos)?;
+        self.pos += 1;
+        match arg.strip_prefix("--").and_then(|opt| opt.split_once('=')) {
+            Some((name, value)) => Some((&arg[..name.len() + 2], Some(value))),
+            None => Some((arg.as_str(), None)),
+        }
+    }
+
+    /// Return the value of the option, either given inline or as the next argument
+    fn value(&mut self, name: &str, inline: Option<&'a str>) -> Result<String, UsageError> {
+        if let Some(value) = inline {
+            return Ok(value.to_string());
+        }
+        let value = self
+            .args
+            .get(self.pos)
+            .ok_or_else(|| usage_error(format!("Option '{}' requires a value", name)))?;
+        self.pos += 1;
+        Ok(value.clone())
+    }
+}
+
+impl Command {
+    /// Parse the command line arguments, without the program name
+    pub fn parse(args: &[String]) -> Result<Self, UsageError> {
+        let Some((name, rest)) = args.split_first() else {
+            return Ok(Command::Help);
+        };
+        let mut reader = ArgReader::new(rest);
+        let mut positional = Vec::new();
+
+        let command = match name.as_str() {
+            "help" | "-h" | "--help" => return Ok(Command::Help),
+            "transform" => {
+                let mut root_attributes = Vec::new();
+                let mut all_attributes = Vec::new();
+                let mut check_end_names = false;
+                let mut watch_on_attribute = None;
+                let mut json = false;
+                while let Some((arg, inline)) = reader.next() {
+                    match arg {
+                        "--root-attr" => root_attributes.push(reader.value(arg, inline)?),
+                        "--attr" => all_attributes.push(reader.value(arg, inline)?),
+                        "--watch" => watch_on_attribute = Some(reader.value(arg, inline)?),
+                        "--check-end-names" => check_end_names = true,
+                        "--json" => json = true,
+                        _ => positional.push(option_or_positional(arg)?),
+                    }
+                }
+                let [input] = expect_positional(positional, &["FILE"])?;
+                Command::Transform {
+                    input: Input::from_arg(&input),
+                    root_attributes,
+                    all_attributes,
+                    check_end_names,
+                    watch_on_attribute,
+                    json,
+                }
+            }
+            "scope-css" => {
+                while let Some((arg, _)) = reader.next() {
+                    positional.push(option_or_positional(arg)?);
+                }
+                let [input, scope_attribute] =
+                    expect_positional(positional, &["FILE", "ATTRIBUTE"])?;
+                Command::ScopeCss {
+                    input: Input::from_arg(&input),
+                    scope_attribute,
+                }
+            }
+            "minify-css" | "js-deps" => {
+                while let Some((arg, _)) = reader.next() {
+                    positional.push(option_or_positional(arg)?);
+                }
+                let [input] = expect_positional(positional, &["FILE"])?;
+                let input = Input::from_arg(&input);
+                if name == "minify-css" {
+                    Command::MinifyCss { input }
+                } else {
+                    Command::JsDeps { input }
+                }
+            }
+            "assets" => {
+                while let Some((arg, _)) = reader.next() {
+                    positional.push(option_or_positional(arg)?);
+                }
+                if positional.is_empty() {
+                    return Err(usage_error("Missing argument 'FILE'"));
+                }
+                Command::Assets {
+                    inputs: positional.iter().map(|arg| Input::from_arg(arg)).collect(),
+                }
+            }
+            "check" => {
+                let mut check_end_names = false;
+                while let Some((arg, _)) = reader.next() {
+                    match arg {
+                        "--check-end-names" => check_end_names = true,
+                        _ => positional.push(option_or_positional(arg)?),
+                    }
+                }
+                if positional.is_empty() {
+                    return Err(usage_error("Missing argument 'PATH'"));
+                }
+                Command::Check {
+                    paths: positional.into_iter().map(PathBuf::from).collect(),
+                    check_end_names,
+                }
+            }
+            _ => return Err(usage_error(format!("Unknown command '{}'", name))),
+        };
+        Ok(command)
+    }
+}
+
+fn option_or_positional(arg: &str) -> Result<String, UsageError> {
+    if arg.starts_with("--") || (arg.starts_with('-') && arg != "-") {
+        return Err(usage_error(format!("Unknown option '{}'", arg)));
+    }
+    Ok(arg.to_string())
+}
+
+fn expect_positional<const N: usize>(
+    positional: Vec<String>,
+    names: &[&str; N],
+) -> Result<[String; N], UsageError> {
+    if positional.len() > N {
+        return Err(usage_error(format!(
+            "Unexpected argument '{}'",
+            positional[N]
+        )));
+    }
+    if positional.len() < N {
+        return Err(usage_error(format!(
+            "Missing argument '{}'",
+            names[positional.len()]
+        )));
+    }
+    Ok(positional.try_into().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Command, UsageError> {
+        let args: Vec<String> = args.iter().map(|s| s.to_string()).collect();
+        Command::parse(&args)
+    }
+
+    #[test]
+    fn test_transform() {
+        assert_eq!(
+            parse(&[
+                "transform",
+                "--root-attr",
+                "data-root",
+                "page.html",
+                "--attr=data-v-1",
+                "--attr",
+                "data-v-2",
+                "--watch",
+                "data-id",
+                "--check-end-names",
+            ]),
+            Ok(Command::Transform {
+                input: Input::File(PathBuf::from("page.html")),
+                root_attributes: vec!["data-root".to_string()],
+                all_attributes: vec!["data-v-1".to_string(), "data-v-2".to_string()],
+                check_end_names: true,
+                watch_on_attribute: Some("data-id".to_string()),
+                json: false,
+            })
+        );
+    }
+
+    #[test]
+    fn test_other_commands() {
+        assert_eq!(parse(&[]), Ok(Command::Help));
+        assert_eq!(parse(&["--help"]), Ok(Command::Help));
+        assert_eq!(
+            parse(&["scope-css", "-", "data-djc-c1"]),
+            Ok(Command::ScopeCss {
+                input: Input::Stdin,
+                scope_attribute: "data-djc-c1".to_string(),
+            })
+        );
+        assert_eq!(
+            parse(&["js-deps", "app.js"]),
+            Ok(Command::JsDeps {
+                input: Input::File(PathBuf::from("app.js")),
+            })
+        );
+        assert_eq!(
+            parse(&["assets", "a.html", "-"]),
+            Ok(Command::Assets {
+                inputs: vec![Input::File(PathBuf::from("a.html")), Input::Stdin],
+            })
+        );
+        assert_eq!(
+            parse(&["check", "templates", "static"]),
+            Ok(Command::Check {
+                paths: vec![PathBuf::from("templates"), PathBuf::from("static")],
+                check_end_names: false,
+            })
+        );
+        assert_eq!(
+            parse(&["check", "--check-end-names", "templates"]),
+            Ok(Command::Check {
+                paths: vec![PathBuf::from("templates")],
+                check_end_names: true,
+            })
+        );
+    }
+
+    #[test]
+    fn test_usage_errors() {
+        let cases: [(&[&str], &str); 7] = [
+            (&["foo"], "Unknown command 'foo'"),
+            (&["transform"], "Missing argument 'FILE'"),
+            (
+                &["transform", "a.html", "--attr"],
+                "Option '--attr' requires a value",
+            ),
+            (&["transform", "a.html", "--foo"], "Unknown option '--foo'"),
+            (&["scope-css", "a.css"], "Missing argument 'ATTRIBUTE'"),
+            (&["assets"], "Missing argument 'FILE'"),
+            (
+                &["minify-css", "a.css", "b.css"],
+                "Unexpected argument 'b.css'",
+            ),
+        ];
+        for (args, message) in cases {
+            assert_eq!(
+                parse(args),
+                Err(UsageError(message.to_string())),
+                "{:?}",
+                args
+            );
+        }
+    }
+}
@@ -0,0 +1,176 @@
+use djc_css_transformer::minify_css;
+use djc_html_transformer::{transform_html_file, HtmlTransformerConfig};
+use djc_js_analyzer::extract_js_dependencies;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// Directories that never contain the project's own sources
+const SKIPPED_DIRS: [&str; 3] = ["node_modules", "__pycache__", "target"];
+
+/// Problem found in one of the checked files
+#[derive(Debug)]
+pub struct CheckError {
+    pub path: PathBuf,
+    pub message: String,
+}
+
+/// Result of checking files in directories
+#[derive(Debug, Default)]
+pub struct CheckReport {
+    /// Number of files that were checked
+    pub checked: usize,
+    pub errors: Vec<CheckError>,
+}
+
+/// Check that all HTML, CSS and JS files in the given paths can be processed.
+///
+/// Directories are searched recursively, skipping hidden directories and directories
+/// like `node_modules`. Files with other extensions are ignored, unless given explicitly.
+///
+/// HTML is parsed leniently, like browsers do, unless `check_end_names` is set, in which
+/// case end tags that don't match their start tags are reported as errors.
+pub fn check_paths(paths: &[PathBuf], check_end_names: bool) -> io::Result<CheckReport> {
+    let mut files = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            collect_files(path, &mut files)?;
+        } else {
+            files.push(path.clone());
+        }
+    }
+
+    let mut report = CheckReport::default();
+    for path in files {
+        report.checked += 1;
+        if let Err(message) = check_file(&path, check_end_names) {
+            report.errors.push(CheckError { path, message });
+        }
+    }
+    Ok(report)
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    let mut entries = fs::read_dir(dir)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<io::Result<Vec<_>>>()?;
+    // Report files in a stable order
+    entries.sort();
+
+    for path in entries {
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy())
+            .unwrap_or_default();
+        if path.is_dir() {
+            if !name.starts_with('.') && !SKIPPED_DIRS.contains(&name.as_ref()) {
+                collect_files(&path, files)?;
+            }
+        } else if file_kind(&path).is_some() {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FileKind {
+    Html,
+    Css,
+    Js,
+}
+
+fn file_kind(path: &Path) -> Option<FileKind> {
+    let extension = path.extension()?.to_string_lossy().to_lowercase();
+    match extension.as_str() {
+        "html" | "htm" => Some(FileKind::Html),
+        "css" => Some(FileKind::Css),
+        "js" | "mjs" => Some(FileKind::Js),
+        _ => None,
+    }
+}
+
+fn check_file(path: &Path, check_end_names: bool) -> Result<(), String> {
+    let kind = file_kind(path).ok_or_else(|| "Unsupported file type".to_string())?;
+    match kind {
+        FileKind::Html => {
+            let config = HtmlTransformerConfig::new(vec![], vec![], check_end_names, None);
+            transform_html_file(path, &config).map_err(|e| e.to_string())?;
+        }
+        FileKind::Css => {
+            let css = fs::read_to_string(path).map_err(|e| e.to_string())?;
+            minify_css(&css).map_err(|e| e.to_string())?;
+        }
+        FileKind::Js => {
+            let js = fs::read_to_string(path).map_err(|e| e.to_string())?;
+            extract_js_dependencies(&js).map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Check the files in a temporary directory and return the paths that failed
+    fn failed_paths(files: &[(&str, &str)], check_end_names: bool) -> (usize, Vec<PathBuf>) {
+        let root = std::env::temp_dir().join(format!(
+            "djc-cli-check-{}-{}",
+            std::process::id(),
+            check_end_names
+        ));
+        for (name, content) in files {
+            let path = root.join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+
+        let report = check_paths(std::slice::from_ref(&root), check_end_names);
+        fs::remove_dir_all(&root).unwrap();
+        let report = report.unwrap();
+
+        let failed = report
+            .errors
+            .iter()
+            .map(|e| e.path.strip_prefix(&root).unwrap().to_path_buf())
+            .collect();
+        (report.checked, failed)
+    }
+
+    #[test]
+    fn test_check_paths() {
+        let files = [
+            ("templates/ok.html", "<div><p>Hi</p></div>"),
+            ("templates/readme.txt", "<div>"),
+            ("static/ok.css", ".a { color: red }"),
+            ("static/bad.js", "import x from 'a"),
+            ("static/.cache/bad.css", "/* unclosed"),
+            ("node_modules/lib/bad.js", "'unclosed"),
+        ];
+
+        let (checked, failed) = failed_paths(&files, false);
+        assert_eq!(checked, 3);
+        assert_eq!(failed, vec![PathBuf::from("static/bad.js")]);
+    }
+
+    #[test]
+    fn test_check_end_names() {
+        let files = [
+            ("list.html", "<ul><li>a<li>b</ul>"),
+            ("mismatched.html", "<div><p>Hi</div></span>"),
+        ];
+
+        // Optional end tags are fine, as in browsers
+        let (checked, failed) = failed_paths(&files, false);
+        assert_eq!(checked, 2);
+        assert_eq!(failed, Vec::<PathBuf>::new());
+
+        // Every end tag has to be written out and match its start tag
+        let (_, failed) = failed_paths(&files, true);
+        assert_eq!(
+            failed,
+            vec![PathBuf::from("list.html"), PathBuf::from("mismatched.html")]
+        );
+    }
+}
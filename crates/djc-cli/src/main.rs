@@ -0,0 +1,198 @@
+use djc_css_transformer::{minify_css, scope_css, CssScopeConfig};
+use djc_html_transformer::encoding::decode_html;
+use djc_html_transformer::{
+    extract_html_assets, set_html_attributes, transform_html_file, Asset, HtmlTransformerConfig,
+    TransformResult,
+};
+use djc_js_analyzer::extract_js_dependencies;
+use serde_json::{json, Map, Value};
+use std::error::Error;
+use std::io::{self, Read};
+use std::process::ExitCode;
+
+mod args;
+mod check;
+
+use args::{Command, Input};
+
+// Command line interface to the Rust crates, so they can be used e.g. in CI pipelines
+// or editors without a Python environment.
+
+const USAGE: &str = "\
+Usage: djc <COMMAND> [OPTIONS]
+
+Commands:
+  transform <FILE>              Add attributes to the HTML elements and print the HTML
+      --root-attr <NAME>        Attribute to add to root elements (repeatable)
+      --attr <NAME>             Attribute to add to all elements (repeatable)
+      --watch <NAME>            Capture attributes added to elements with this attribute
+      --check-end-names         Check that end tags match start tags
+      --json                    Print {\"html\": ..., \"captured\": ...} as JSON
+  scope-css <FILE> <ATTRIBUTE>  Scope the CSS selectors with the attribute and print the CSS
+  minify-css <FILE>             Minify the CSS and print it
+  js-deps <FILE>                Print the imports of the JS file as JSON
+  assets <FILE>...              Print the scripts, stylesheets and images of the HTML files
+                                as a JSON manifest
+  check <PATH>...               Check that all HTML, CSS and JS files in the paths can be processed
+      --check-end-names         Check that end tags match start tags
+  help                          Print this message
+
+Use `-` as FILE to read from stdin.";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let command = match Command::parse(&args) {
+        Ok(command) => command,
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    match run(command) {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(command: Command) -> Result<ExitCode, Box<dyn Error>> {
+    match command {
+        Command::Help => println!("{}", USAGE),
+        Command::Transform {
+            input,
+            root_attributes,
+            all_attributes,
+            check_end_names,
+            watch_on_attribute,
+            json,
+        } => {
+            let config = HtmlTransformerConfig::new(
+                root_attributes,
+                all_attributes,
+                check_end_names,
+                watch_on_attribute,
+            );
+            // Files are decoded based on their BOM or `<meta charset>`
+            let result = match input {
+                Input::Stdin => set_html_attributes(&read_stdin()?, &config)?,
+                Input::File(path) => transform_html_file(path, &config)?,
+            };
+            if json {
+                println!("{}", transform_result_to_json(result));
+            } else {
+                print!("{}", result.html);
+            }
+        }
+        Command::ScopeCss {
+            input,
+            scope_attribute,
+        } => {
+            let config = CssScopeConfig::new(scope_attribute);
+            print!("{}", scope_css(&read_input(&input)?, &config)?);
+        }
+        Command::MinifyCss { input } => println!("{}", minify_css(&read_input(&input)?)?),
+        Command::JsDeps { input } => {
+            let dependencies = extract_js_dependencies(&read_input(&input)?)?;
+            let dependencies: Vec<Value> = dependencies
+                .into_iter()
+                .map(|dep| {
+                    json!({
+                        "specifier": dep.specifier,
+                        "kind": dep.kind.as_str(),
+                        "specifier_kind": dep.specifier_kind.as_str(),
+                        "names": dep.names,
+                        "start": dep.start,
+                        "end": dep.end,
+                        "specifier_start": dep.specifier_start,
+                        "specifier_end": dep.specifier_end,
+                    })
+                })
+                .collect();
+            println!("{}", serde_json::to_string_pretty(&dependencies)?);
+        }
+        Command::Assets { inputs } => {
+            let mut manifest = Map::new();
+            for input in inputs {
+                let (name, html) = match input {
+                    Input::Stdin => ("-".to_string(), read_stdin()?),
+                    Input::File(path) => {
+                        // Decode the same way as `transform` does
+                        let bytes = std::fs::read(&path)?;
+                        let html = decode_html(&bytes).0.into_owned();
+                        (path.display().to_string(), html)
+                    }
+                };
+                let assets = extract_html_assets(&html)
+                    .map_err(|e| format!("{}: {}", name, e))?
+                    .into_iter()
+                    .map(asset_to_json)
+                    .collect();
+                manifest.insert(name, Value::Array(assets));
+            }
+            println!("{}", serde_json::to_string_pretty(&manifest)?);
+        }
+        Command::Check {
+            paths,
+            check_end_names,
+        } => {
+            let report = check::check_paths(&paths, check_end_names)?;
+            for error in &report.errors {
+                println!("{}: {}", error.path.display(), error.message);
+            }
+            eprintln!(
+                "Checked {}, found {}",
+                plural(report.checked, "file"),
+                plural(report.errors.len(), "error")
+            );
+            if !report.errors.is_empty() {
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Format a count with the noun, e.g. `1 file` or `2 files`
+fn plural(count: usize, noun: &str) -> String {
+    if count == 1 {
+        format!("{} {}", count, noun)
+    } else {
+        format!("{} {}s", count, noun)
+    }
+}
+
+fn read_stdin() -> io::Result<String> {
+    let mut text = String::new();
+    io::stdin().read_to_string(&mut text)?;
+    Ok(text)
+}
+
+fn read_input(input: &Input) -> io::Result<String> {
+    match input {
+        Input::Stdin => read_stdin(),
+        Input::File(path) => std::fs::read_to_string(path),
+    }
+}
+
+fn transform_result_to_json(result: TransformResult) -> Value {
+    let captured: Map<String, Value> = result
+        .captured
+        .into_iter()
+        .map(|(id, attrs)| (id, json!(attrs)))
+        .collect();
+    json!({ "html": result.html, "captured": captured })
+}
+
+fn asset_to_json(asset: Asset) -> Value {
+    json!({
+        "kind": asset.kind.as_str(),
+        "url": asset.url,
+        "rel": asset.rel,
+        "hash": asset.hash.map(|hash| format!("{:032x}", hash)),
+        "start": asset.start,
+        "end": asset.end,
+    })
+}
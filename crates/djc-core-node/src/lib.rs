@@ -0,0 +1,114 @@
+use djc_css_transformer::{
+    minify_css as minify_css_rust, scope_css as scope_css_rust, CssScopeConfig,
+};
+use djc_html_transformer::{
+    set_html_attributes as set_html_attributes_rust, HtmlTransformerConfig,
+};
+use djc_js_analyzer::extract_js_dependencies as extract_js_dependencies_rust;
+use napi::{Env, Error, JsObject, Result};
+use napi_derive::napi;
+
+// Thin Node.js API over the other Rust crates, mirroring the WebAssembly API of djc-core-wasm.
+// Build with `napi build --platform --release` in `crates/djc-core-node`.
+
+/// A module that the JS imports or re-exports from.
+///
+/// Positions are UTF-16 offsets, so they can be used to index into JS strings.
+#[napi(object)]
+pub struct JsDependency {
+    pub specifier: String,
+    /// One of `import`, `side_effect`, `dynamic`, `reexport`
+    pub kind: String,
+    /// One of `bare`, `relative`, `absolute`, `url`
+    pub specifier_kind: String,
+    pub names: Vec<String>,
+    pub start: u32,
+    pub end: u32,
+    pub specifier_start: u32,
+    pub specifier_end: u32,
+}
+
+/// Transform HTML by adding attributes to the elements.
+///
+/// Returns an object `{ html, captured }`, where `captured` maps the values of the watched
+/// attribute to the attributes that were added to those elements.
+///
+/// Throws an `Error` if the HTML is malformed or cannot be parsed.
+#[napi(ts_return_type = "{ html: string, captured: Record<string, string[]> }")]
+pub fn set_html_attributes(
+    env: Env,
+    html: String,
+    root_attributes: Vec<String>,
+    all_attributes: Vec<String>,
+    check_end_names: Option<bool>,
+    watch_on_attribute: Option<String>,
+) -> Result<JsObject> {
+    let config = HtmlTransformerConfig::new(
+        root_attributes,
+        all_attributes,
+        check_end_names.unwrap_or(false),
+        watch_on_attribute,
+    );
+
+    let result = set_html_attributes_rust(&html, &config).map_err(to_napi_error)?;
+
+    // Build the object by hand to keep the order of the captured attributes
+    let mut captured = env.create_object()?;
+    for (id, attrs) in result.captured {
+        captured.set(id, attrs)?;
+    }
+
+    let mut output = env.create_object()?;
+    output.set("html", result.html)?;
+    output.set("captured", captured)?;
+    Ok(output)
+}
+
+/// Scope CSS to a component by appending an attribute selector to all selectors.
+///
+/// Throws an `Error` if the CSS is malformed, e.g. has unclosed blocks.
+#[napi]
+pub fn scope_css(css: String, scope_attribute: String) -> Result<String> {
+    let config = CssScopeConfig::new(scope_attribute);
+    scope_css_rust(&css, &config).map_err(to_napi_error)
+}
+
+/// Minify CSS by removing comments and insignificant whitespace.
+///
+/// Throws an `Error` if the CSS is malformed, e.g. has unclosed strings or comments.
+#[napi]
+pub fn minify_css(css: String) -> Result<String> {
+    minify_css_rust(&css).map_err(to_napi_error)
+}
+
+/// Find all modules that the JS imports or re-exports from.
+///
+/// Throws an `Error` if the JS cannot be tokenized, e.g. has unclosed strings or comments.
+#[napi]
+pub fn extract_js_dependencies(js: String) -> Result<Vec<JsDependency>> {
+    let dependencies = extract_js_dependencies_rust(&js).map_err(to_napi_error)?;
+
+    let result = dependencies
+        .into_iter()
+        .map(|dep| JsDependency {
+            specifier: dep.specifier,
+            kind: dep.kind.as_str().to_string(),
+            specifier_kind: dep.specifier_kind.as_str().to_string(),
+            names: dep.names,
+            start: utf16_offset(&js, dep.start),
+            end: utf16_offset(&js, dep.end),
+            specifier_start: utf16_offset(&js, dep.specifier_start),
+            specifier_end: utf16_offset(&js, dep.specifier_end),
+        })
+        .collect();
+    Ok(result)
+}
+
+fn to_napi_error(e: impl ToString) -> Error {
+    Error::from_reason(e.to_string())
+}
+
+/// Convert a byte offset into the string to a UTF-16 offset, as used by JS strings
+fn utf16_offset(text: &str, byte_offset: usize) -> u32 {
+    text[..byte_offset].encode_utf16().count() as u32
+}
@@ -0,0 +1,41 @@
+const assert = require("node:assert");
+const { test } = require("node:test");
+
+const {
+  setHtmlAttributes,
+  scopeCss,
+  minifyCss,
+  extractJsDependencies,
+} = require("../index.js");
+
+test("setHtmlAttributes", () => {
+  const result = setHtmlAttributes(
+    '<div data-id="123"><p>Hello</p></div>',
+    ["data-root"],
+    ["data-v-123"],
+    false,
+    "data-id",
+  );
+  assert.deepStrictEqual(result, {
+    html: '<div data-id="123" data-root="" data-v-123=""><p data-v-123="">Hello</p></div>',
+    captured: { 123: ["data-root", "data-v-123"] },
+  });
+
+  assert.throws(() => setHtmlAttributes("<div></span>", [], [], true), /expected `<\/div>`/);
+});
+
+test("scopeCss and minifyCss", () => {
+  assert.strictEqual(scopeCss(".btn > p:hover { color: red }", "data-s"), ".btn > p[data-s]:hover { color: red }");
+  assert.strictEqual(minifyCss(".btn > p { margin: 0px 0.5em; }"), ".btn>p{margin:0 .5em}");
+});
+
+test("extractJsDependencies uses UTF-16 offsets", () => {
+  const js = '/* 😀 */ import { a } from "lib";';
+  const [dep] = extractJsDependencies(js);
+  assert.strictEqual(dep.specifier, "lib");
+  assert.strictEqual(dep.kind, "import");
+  assert.strictEqual(dep.specifierKind, "bare");
+  assert.deepStrictEqual(dep.names, ["a"]);
+  assert.strictEqual(js.slice(dep.specifierStart, dep.specifierEnd), '"lib"');
+  assert.strictEqual(js.slice(dep.start, dep.end), 'import { a } from "lib";');
+});
@@ -0,0 +1,123 @@
+use djc_css_transformer::{
+    minify_css as minify_css_rust, scope_css as scope_css_rust, CssScopeConfig,
+};
+use djc_html_transformer::{
+    enabled_features, set_html_attributes as set_html_attributes_rust, HtmlTransformerConfig,
+};
+use djc_js_analyzer::extract_js_dependencies as extract_js_dependencies_rust;
+use js_sys::{Array, Object, Reflect};
+use wasm_bindgen::prelude::*;
+
+// Thin WebAssembly API over the other Rust crates, mirroring the Python API of djc-core.
+// Build with `wasm-pack build crates/djc-core-wasm`.
+
+/// Transform HTML by adding attributes to the elements.
+///
+/// Returns an object `{ html, captured }`, where `captured` maps the values of the watched
+/// attribute to the attributes that were added to those elements.
+///
+/// Throws an `Error` if the HTML is malformed or cannot be parsed.
+#[wasm_bindgen(js_name = setHtmlAttributes)]
+pub fn set_html_attributes(
+    html: &str,
+    root_attributes: Vec<String>,
+    all_attributes: Vec<String>,
+    check_end_names: Option<bool>,
+    watch_on_attribute: Option<String>,
+) -> Result<JsValue, JsError> {
+    let config = HtmlTransformerConfig::new(
+        root_attributes,
+        all_attributes,
+        check_end_names.unwrap_or(false),
+        watch_on_attribute,
+    );
+
+    let result =
+        set_html_attributes_rust(html, &config).map_err(|e| JsError::new(&e.to_string()))?;
+
+    let captured = Object::new();
+    for (id, attrs) in result.captured {
+        let attrs: Array = attrs.into_iter().map(JsValue::from).collect();
+        set(&captured, &id, attrs.into())?;
+    }
+
+    let output = Object::new();
+    set(&output, "html", result.html.into())?;
+    set(&output, "captured", captured.into())?;
+    Ok(output.into())
+}
+
+/// Scope CSS to a component by appending an attribute selector to all selectors.
+///
+/// Throws an `Error` if the CSS is malformed, e.g. has unclosed blocks.
+#[wasm_bindgen(js_name = scopeCss)]
+pub fn scope_css(css: &str, scope_attribute: String) -> Result<String, JsError> {
+    let config = CssScopeConfig::new(scope_attribute);
+    scope_css_rust(css, &config).map_err(|e| JsError::new(&e.to_string()))
+}
+
+/// Minify CSS by removing comments and insignificant whitespace.
+///
+/// Throws an `Error` if the CSS is malformed, e.g. has unclosed strings or comments.
+#[wasm_bindgen(js_name = minifyCss)]
+pub fn minify_css(css: &str) -> Result<String, JsError> {
+    minify_css_rust(css).map_err(|e| JsError::new(&e.to_string()))
+}
+
+/// Find all modules that the JS imports or re-exports from.
+///
+/// Returns an array of objects with keys `specifier`, `kind`, `specifierKind`, `names`,
+/// `start`, `end`, `specifierStart` and `specifierEnd`. Positions are UTF-16 offsets,
+/// so they can be used to index into JS strings.
+///
+/// Throws an `Error` if the JS cannot be tokenized, e.g. has unclosed strings or comments.
+#[wasm_bindgen(js_name = extractJsDependencies)]
+pub fn extract_js_dependencies(js: &str) -> Result<Array, JsError> {
+    let dependencies =
+        extract_js_dependencies_rust(js).map_err(|e| JsError::new(&e.to_string()))?;
+
+    let result = Array::new();
+    for dep in dependencies {
+        let names: Array = dep.names.into_iter().map(JsValue::from).collect();
+        let obj = Object::new();
+        set(&obj, "specifier", dep.specifier.into())?;
+        set(&obj, "kind", dep.kind.as_str().into())?;
+        set(&obj, "specifierKind", dep.specifier_kind.as_str().into())?;
+        set(&obj, "names", names.into())?;
+        set(&obj, "start", utf16_offset(js, dep.start).into())?;
+        set(&obj, "end", utf16_offset(js, dep.end).into())?;
+        set(&obj, "specifierStart", utf16_offset(js, dep.specifier_start).into())?;
+        set(&obj, "specifierEnd", utf16_offset(js, dep.specifier_end).into())?;
+        result.push(&obj);
+    }
+    Ok(result)
+}
+
+/// Get the version of the package and the optional features it was built with.
+///
+/// Returns an object `{ version, features }`, where `features` maps the feature names
+/// (`cache`, `parallel`, `wasm`) to whether they are enabled.
+#[wasm_bindgen]
+pub fn features() -> Result<JsValue, JsError> {
+    let enabled = enabled_features();
+    let features = Object::new();
+    set(&features, "cache", enabled.contains(&"cache").into())?;
+    set(&features, "parallel", enabled.contains(&"parallel").into())?;
+    set(&features, "wasm", true.into())?;
+
+    let output = Object::new();
+    set(&output, "version", env!("CARGO_PKG_VERSION").into())?;
+    set(&output, "features", features.into())?;
+    Ok(output.into())
+}
+
+fn set(target: &Object, key: &str, value: JsValue) -> Result<(), JsError> {
+    Reflect::set(target, &JsValue::from_str(key), &value)
+        .map(|_| ())
+        .map_err(|_| JsError::new(&format!("Failed to set property '{}'", key)))
+}
+
+/// Convert a byte offset into the string to a UTF-16 offset, as used by JS strings
+fn utf16_offset(text: &str, byte_offset: usize) -> u32 {
+    text[..byte_offset].encode_utf16().count() as u32
+}
@@ -1,15 +1,106 @@
+use djc_css_transformer::{
+    minify_css as minify_css_rust, scope_css as scope_css_rust, CssScopeConfig,
+};
+use djc_html_transformer::encoding::decode_html;
 use djc_html_transformer::{
-    set_html_attributes as set_html_attributes_rust, HtmlTransformerConfig,
+    enabled_features, extract_html_assets as extract_html_assets_rust, hash_html as hash_html_rust,
+    hash_template as hash_template_rust, set_html_attributes_many as set_html_attributes_many_rust,
+    set_html_attributes_many_parallel as set_html_attributes_many_parallel_rust,
+    transformer::transform, AttributeOrder, BatchResult, HtmlTransformerConfig, HtmxAttributes,
+    TransformCache, TransformError, TransformWarning,
 };
-use pyo3::exceptions::{PyValueError};
+use djc_js_analyzer::extract_js_dependencies as extract_js_dependencies_rust;
+use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
-use pyo3::types::{PyDict, PyTuple};
+use pyo3::types::{PyDict, PyList};
+use result::HtmlTransformResult;
+use stats::Timer;
+use std::path::{Path, PathBuf};
+
+mod logging;
+mod result;
+mod stats;
+
+pyo3::create_exception!(
+    djc_core,
+    DjcHtmlRecoveryWarning,
+    pyo3::exceptions::PyUserWarning,
+    "Issued when the HTML is malformed, but could still be transformed, e.g. has unclosed tags.\n\n\
+     The `line` and `column` attributes hold the 1-based position of the problem in the HTML,\n\
+     and `filename` the file the HTML was read from, if any."
+);
+
+pyo3::create_exception!(
+    djc_core,
+    HtmlTransformError,
+    PyValueError,
+    "Raised when the HTML is malformed and cannot be transformed.\n\n\
+     The `line` and `column` attributes hold the 1-based position of the problem, `offset`\n\
+     the position in characters, and `source_snippet` the line with the problem.\n\
+     `filename` is the file the HTML was read from, if any."
+);
+
+// Keyword arguments that configure the HTML transformer, same for `set_html_attributes()`,
+// `set_html_attributes_many()` and `transform_html_file()`. Reported by `features()`.
+const HTML_TRANSFORMER_OPTIONS: [&str; 13] = [
+    "check_end_names",
+    "watch_on_attribute",
+    "source_map",
+    "attribute_order",
+    "repair_misnested",
+    "xml",
+    "csrf_token",
+    "htmx_attributes",
+    "htmx_selectors",
+    "preserve_directives",
+    "strip_comments",
+    "keep_comments",
+    "collapse_whitespace",
+];
+
+// Results of `set_html_attributes()`, disabled until enabled with `configure_cache()`
+static HTML_CACHE: TransformCache = TransformCache::disabled();
 
 /// Singular Python API that brings togther all the other Rust crates.
-#[pymodule]
+///
+/// The only global state are the cache of results and the stats, which are guarded by mutexes,
+/// so the module is safe to use on free-threaded (no-GIL) Python builds.
+#[pymodule(gil_used = false)]
 fn djc_core(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    stats::init_from_env();
+
     // HTML transformer
+    m.add(
+        "DjcHtmlRecoveryWarning",
+        m.py().get_type::<DjcHtmlRecoveryWarning>(),
+    )?;
+    m.add(
+        "HtmlTransformError",
+        m.py().get_type::<HtmlTransformError>(),
+    )?;
+    m.add_class::<HtmlTransformResult>()?;
     m.add_function(wrap_pyfunction!(set_html_attributes, m)?)?;
+    m.add_function(wrap_pyfunction!(set_html_attributes_many, m)?)?;
+    m.add_function(wrap_pyfunction!(transform_html_file, m)?)?;
+    m.add_function(wrap_pyfunction!(configure_cache, m)?)?;
+    m.add_function(wrap_pyfunction!(cache_info, m)?)?;
+    m.add_function(wrap_pyfunction!(cache_clear, m)?)?;
+    m.add_function(wrap_pyfunction!(hash_html, m)?)?;
+    m.add_function(wrap_pyfunction!(hash_template, m)?)?;
+    m.add_function(wrap_pyfunction!(extract_html_assets, m)?)?;
+    // CSS transformer
+    m.add_function(wrap_pyfunction!(scope_css, m)?)?;
+    m.add_function(wrap_pyfunction!(minify_css, m)?)?;
+    // JS analyzer
+    m.add_function(wrap_pyfunction!(extract_js_dependencies, m)?)?;
+    // Stats
+    m.add_function(wrap_pyfunction!(get_stats, m)?)?;
+    m.add_function(wrap_pyfunction!(enable_stats, m)?)?;
+    m.add_function(wrap_pyfunction!(reset_stats, m)?)?;
+    // Logging
+    m.add_function(wrap_pyfunction!(enable_logging, m)?)?;
+    // Introspection
+    m.add_function(wrap_pyfunction!(features, m)?)?;
     Ok(())
 }
 
@@ -21,26 +112,65 @@ fn djc_core(m: &Bound<'_, PyModule>) -> PyResult<()> {
 ///     all_attributes (List[str]): List of attribute names to add to all elements.
 ///     check_end_names (bool, optional): Whether to validate matching of end tags. Defaults to false.
 ///     watch_on_attribute (str, optional): If set, captures which attributes were added to elements with this attribute.
+///     source_map (bool, optional): If set, also returns a mapping of the output back to the input. Defaults to false.
+///     attribute_order (str | List[str], optional): Order of the attributes of the elements that attributes are added to.
+///         `"original"` (default) keeps the existing attributes first, `"alphabetical"` sorts them by name,
+///         and a list of attribute names puts those first, followed by the rest in the original order.
+///     repair_misnested (bool, optional): Whether to repair misnested and unclosed tags the way browsers do,
+///         instead of keeping them as they are. Ignored with `check_end_names`. Defaults to false.
+///     xml (bool, optional): Whether to parse the input as XML, e.g. SVG, instead of HTML. Tag names
+///         are case-sensitive, there are no void elements, and misnested or unclosed tags raise an error.
+///         Defaults to false.
+///     csrf_token (str, optional): If set, a hidden `csrfmiddlewaretoken` input with this value is inserted
+///         into every `<form method="post">` that doesn't have it yet.
+///     htmx_attributes (Dict[str, Any], optional): `hx-*` attributes to add to the root elements, e.g.
+///         `{'hx-target': 'this'}`. Values that are not strings, e.g. a dict for `hx-headers`, are serialized
+///         as JSON. Attributes that the element already has are not added.
+///     htmx_selectors (List[str], optional): Add the `htmx_attributes` to the elements that match any of
+///         these selectors instead of the root elements. Only tag names, `#id`, `.class` and `[attribute]`
+///         are supported, e.g. `'button.save'`.
+///     preserve_directives (bool, optional): Whether to keep the attributes of frontend frameworks, e.g. `@click`,
+///         `:class` or `x-on:click.prevent`, exactly as they are written when the attributes are rewritten
+///         in the `attribute_order`. Defaults to false.
+///     strip_comments (bool, optional): Whether to remove HTML comments, except those that match `keep_comments`.
+///         Defaults to false.
+///     keep_comments (List[str], optional): Patterns of the comments to keep when stripping comments, matched
+///         against the content of the comment without the surrounding whitespace. `*` matches any text.
+///         Defaults to conditional comments and django-components markers:
+///         `['[if *', '<![endif]*', '_RENDERED *', 'djc-*']`.
+///     collapse_whitespace (bool, optional): Whether to collapse whitespace between block-level tags into
+///         a single line break or space. Whitespace next to text or inline elements, and inside `<pre>`,
+///         `<textarea>`, `<script>` and `<style>` is kept. Defaults to false.
 ///
 /// Returns:
-///     Tuple[str, Dict[str, List[str]]]: A tuple containing:
-///         - The transformed HTML string
-///         - A dictionary mapping captured attribute values to lists of attributes that were added
-///           to those elements. Only returned if watch_on_attribute is set, otherwise empty dict.
+///     HtmlTransformResult: An object with the attributes:
+///         - `html`: The transformed HTML string
+///         - `captured`: A dictionary mapping captured attribute values to lists of attributes that
+///           were added to those elements. Only filled if watch_on_attribute is set, otherwise empty dict.
+///         - `source_map`: If `source_map` is set, a JSON-serializable dictionary
+///           `{"version": 1, "mappings": [[output_start, output_end, source_start, source_end], ...]}`,
+///           otherwise None.
+///         - `roots`: Tag names of the root elements.
+///         - `stats`: Number of `elements`, and of attributes added to them (`attributes_added`).
+///         - `warnings`: The issued `DjcHtmlRecoveryWarning` warnings. Results returned from the cache
+///           have no warnings, as they were issued when the HTML was first transformed.
+///     The result can also be unpacked as the `(html, captured)` tuple,
+///     or `(html, captured, source_map)` if `source_map` is set.
 ///
 /// Example:
 ///     >>> html = '<div data-id="123"><p>Hello</p></div>'
-///     >>> html, captured = set_html_attributes(html, ['data-root-id'], ['data-v-123'], watch_on_attribute='data-id')
-///     >>> print(captured)
+///     >>> result = set_html_attributes(html, ['data-root-id'], ['data-v-123'], watch_on_attribute='data-id')
+///     >>> print(result.captured)
 ///     {'123': ['data-root-id', 'data-v-123']}
 ///
 /// Raises:
-///     ValueError: If the HTML is malformed or cannot be parsed.
+///     HtmlTransformError: If the HTML is malformed or cannot be parsed. Subclass of `ValueError`.
 #[pyfunction]
-#[pyo3(signature = (html, root_attributes, all_attributes, check_end_names=None, watch_on_attribute=None))]
+#[pyo3(signature = (html, root_attributes, all_attributes, *, check_end_names=None, watch_on_attribute=None, source_map=None, attribute_order=None, repair_misnested=None, xml=None, csrf_token=None, htmx_attributes=None, htmx_selectors=None, preserve_directives=None, strip_comments=None, keep_comments=None, collapse_whitespace=None))]
 #[pyo3(
-    text_signature = "(html, root_attributes, all_attributes, *, check_end_names=False, watch_on_attribute=None)"
+    text_signature = "(html, root_attributes, all_attributes, *, check_end_names=False, watch_on_attribute=None, source_map=False, attribute_order='original', repair_misnested=False, xml=False, csrf_token=None, htmx_attributes=None, htmx_selectors=None, preserve_directives=False, strip_comments=False, keep_comments=None, collapse_whitespace=False)"
 )]
+#[allow(clippy::too_many_arguments)]
 pub fn set_html_attributes(
     py: Python,
     html: &str,
@@ -48,29 +178,801 @@ pub fn set_html_attributes(
     all_attributes: Vec<String>,
     check_end_names: Option<bool>,
     watch_on_attribute: Option<String>,
-) -> PyResult<Py<PyAny>> {
-    let config = HtmlTransformerConfig::new(
+    source_map: Option<bool>,
+    attribute_order: Option<AttributeOrderArg>,
+    repair_misnested: Option<bool>,
+    xml: Option<bool>,
+    csrf_token: Option<String>,
+    htmx_attributes: Option<Bound<'_, PyDict>>,
+    htmx_selectors: Option<Vec<String>>,
+    preserve_directives: Option<bool>,
+    strip_comments: Option<bool>,
+    keep_comments: Option<Vec<String>>,
+    collapse_whitespace: Option<bool>,
+) -> PyResult<HtmlTransformResult> {
+    let config = config_from_py(
         root_attributes,
         all_attributes,
-        check_end_names.unwrap_or(false),
+        check_end_names,
+        watch_on_attribute,
+        source_map,
+        attribute_order,
+        repair_misnested,
+        xml,
+        csrf_token,
+        htmx_attributes,
+        htmx_selectors,
+        preserve_directives,
+        strip_comments,
+        keep_comments,
+        collapse_whitespace,
+    )?;
+
+    let mut timer = Timer::start("set_html_attributes");
+    let result = HTML_CACHE.get_or_transform(&config, html);
+    timer.phase("transform");
+
+    let result = result.map_err(|e| html_error(py, &*e, html, "", None))?;
+
+    let warnings = warn_recovered(py, &result.warnings, "", None)?;
+    let output = HtmlTransformResult::new(py, result, html, warnings)?;
+    timer.phase("to_python");
+    Ok(output)
+}
+
+/// Read a HTML file and transform it by adding attributes to the elements.
+///
+/// The file is read and decoded in Rust, so its content doesn't have to go through Python.
+/// The encoding is detected from the byte order mark (BOM) or from the `<meta charset>`
+/// declaration, and defaults to UTF-8.
+///
+/// Errors and warnings about the HTML start with the path, and have the `filename` attribute set.
+///
+/// Args:
+///     path (str | os.PathLike): Path to the HTML file.
+///     root_attributes (List[str]): List of attribute names to add to root elements only.
+///     all_attributes (List[str]): List of attribute names to add to all elements.
+///     check_end_names (bool, optional): Whether to validate matching of end tags. Defaults to false.
+///     watch_on_attribute (str, optional): If set, captures which attributes were added to elements with this attribute.
+///     attribute_order (str | List[str], optional): Same as in `set_html_attributes()`.
+///     repair_misnested (bool, optional): Same as in `set_html_attributes()`.
+///     xml (bool, optional): Same as in `set_html_attributes()`.
+///     csrf_token (str, optional): Same as in `set_html_attributes()`.
+///     htmx_attributes (Dict[str, Any], optional): Same as in `set_html_attributes()`.
+///     htmx_selectors (List[str], optional): Same as in `set_html_attributes()`.
+///     preserve_directives (bool, optional): Same as in `set_html_attributes()`.
+///     strip_comments (bool, optional): Same as in `set_html_attributes()`.
+///     keep_comments (List[str], optional): Same as in `set_html_attributes()`.
+///     collapse_whitespace (bool, optional): Same as in `set_html_attributes()`.
+///     source_map (bool, optional): Same as in `set_html_attributes()`.
+///
+/// Returns:
+///     HtmlTransformResult: Same as `set_html_attributes()`.
+///
+/// Example:
+///     >>> html, captured = transform_html_file('templates/button.html', ['data-root-id'], ['data-v-123'])
+///
+/// Raises:
+///     OSError: If the file cannot be read, e.g. `FileNotFoundError`.
+///     HtmlTransformError: If the HTML is malformed or cannot be parsed. Subclass of `ValueError`.
+#[pyfunction]
+#[pyo3(signature = (path, root_attributes, all_attributes, *, check_end_names=None, watch_on_attribute=None, attribute_order=None, repair_misnested=None, xml=None, csrf_token=None, htmx_attributes=None, htmx_selectors=None, preserve_directives=None, strip_comments=None, keep_comments=None, collapse_whitespace=None, source_map=None))]
+#[pyo3(
+    text_signature = "(path, root_attributes, all_attributes, *, check_end_names=False, watch_on_attribute=None, attribute_order='original', repair_misnested=False, xml=False, csrf_token=None, htmx_attributes=None, htmx_selectors=None, preserve_directives=False, strip_comments=False, keep_comments=None, collapse_whitespace=False, source_map=False)"
+)]
+#[allow(clippy::too_many_arguments)]
+pub fn transform_html_file(
+    py: Python,
+    path: PathBuf,
+    root_attributes: Vec<String>,
+    all_attributes: Vec<String>,
+    check_end_names: Option<bool>,
+    watch_on_attribute: Option<String>,
+    attribute_order: Option<AttributeOrderArg>,
+    repair_misnested: Option<bool>,
+    xml: Option<bool>,
+    csrf_token: Option<String>,
+    htmx_attributes: Option<Bound<'_, PyDict>>,
+    htmx_selectors: Option<Vec<String>>,
+    preserve_directives: Option<bool>,
+    strip_comments: Option<bool>,
+    keep_comments: Option<Vec<String>>,
+    collapse_whitespace: Option<bool>,
+    source_map: Option<bool>,
+) -> PyResult<HtmlTransformResult> {
+    let config = config_from_py(
+        root_attributes,
+        all_attributes,
+        check_end_names,
+        watch_on_attribute,
+        source_map,
+        attribute_order,
+        repair_misnested,
+        xml,
+        csrf_token,
+        htmx_attributes,
+        htmx_selectors,
+        preserve_directives,
+        strip_comments,
+        keep_comments,
+        collapse_whitespace,
+    )?;
+
+    let mut timer = Timer::start("transform_html_file");
+    // Reading and transforming the file doesn't touch any Python objects.
+    // IO errors are raised as `FileNotFoundError`, `PermissionError`, etc.
+    let (html, result) = py.detach(|| -> std::io::Result<_> {
+        let bytes = std::fs::read(&path)?;
+        // Keep the decoded HTML to report the position of errors in it
+        let html = decode_html(&bytes).0.into_owned();
+        let result = transform(&config, &html);
+        Ok((html, result))
+    })?;
+    timer.phase("read_and_transform");
+    let prefix = format!("{}: ", path.display());
+    let result = result.map_err(|e| html_error(py, &*e, &html, &prefix, Some(&path)))?;
+
+    let warnings = warn_recovered(py, &result.warnings, &prefix, Some(&path))?;
+    let output = HtmlTransformResult::new(py, result, &html, warnings)?;
+    timer.phase("to_python");
+    Ok(output)
+}
+
+/// Transform many HTML strings by adding the same attributes to their elements.
+///
+/// Same as calling `set_html_attributes()` for each of the HTML strings, but the GIL is released
+/// while the HTML is transformed. With `parallel=True`, the HTML strings are transformed
+/// in parallel on a thread pool, e.g. to precompile all templates of a large project.
+///
+/// Args:
+///     htmls (List[str]): The HTML strings to transform.
+///     root_attributes (List[str]): List of attribute names to add to root elements only.
+///     all_attributes (List[str]): List of attribute names to add to all elements.
+///     check_end_names (bool, optional): Whether to validate matching of end tags. Defaults to false.
+///     watch_on_attribute (str, optional): If set, captures which attributes were added to elements with this attribute.
+///     parallel (bool, optional): Whether to transform the HTML strings in parallel. Defaults to false.
+///     num_threads (int, optional): Number of threads to use if `parallel` is set. Defaults to the number of CPUs.
+///     attribute_order (str | List[str], optional): Same as in `set_html_attributes()`.
+///     repair_misnested (bool, optional): Same as in `set_html_attributes()`.
+///     xml (bool, optional): Same as in `set_html_attributes()`.
+///     csrf_token (str, optional): Same as in `set_html_attributes()`.
+///     htmx_attributes (Dict[str, Any], optional): Same as in `set_html_attributes()`.
+///     htmx_selectors (List[str], optional): Same as in `set_html_attributes()`.
+///     preserve_directives (bool, optional): Same as in `set_html_attributes()`.
+///     strip_comments (bool, optional): Same as in `set_html_attributes()`.
+///     keep_comments (List[str], optional): Same as in `set_html_attributes()`.
+///     collapse_whitespace (bool, optional): Same as in `set_html_attributes()`.
+///     source_map (bool, optional): Same as in `set_html_attributes()`.
+///
+/// Returns:
+///     List[HtmlTransformResult]: For each HTML string, in the same order,
+///         the same result as returned by `set_html_attributes()`.
+///
+/// Example:
+///     >>> results = set_html_attributes_many(['<div>A</div>', '<p>B</p>'], [], ['data-v-123'], parallel=True)
+///     >>> [html for html, captured in results]
+///     ['<div data-v-123="">A</div>', '<p data-v-123="">B</p>']
+///
+/// Raises:
+///     HtmlTransformError: If any of the HTML strings is malformed or cannot be parsed. Subclass of `ValueError`.
+#[pyfunction]
+#[pyo3(signature = (htmls, root_attributes, all_attributes, *, check_end_names=None, watch_on_attribute=None, parallel=None, num_threads=None, attribute_order=None, repair_misnested=None, xml=None, csrf_token=None, htmx_attributes=None, htmx_selectors=None, preserve_directives=None, strip_comments=None, keep_comments=None, collapse_whitespace=None, source_map=None))]
+#[pyo3(
+    text_signature = "(htmls, root_attributes, all_attributes, *, check_end_names=False, watch_on_attribute=None, parallel=False, num_threads=None, attribute_order='original', repair_misnested=False, xml=False, csrf_token=None, htmx_attributes=None, htmx_selectors=None, preserve_directives=False, strip_comments=False, keep_comments=None, collapse_whitespace=False, source_map=False)"
+)]
+#[allow(clippy::too_many_arguments)]
+pub fn set_html_attributes_many(
+    py: Python,
+    htmls: Vec<String>,
+    root_attributes: Vec<String>,
+    all_attributes: Vec<String>,
+    check_end_names: Option<bool>,
+    watch_on_attribute: Option<String>,
+    parallel: Option<bool>,
+    num_threads: Option<usize>,
+    attribute_order: Option<AttributeOrderArg>,
+    repair_misnested: Option<bool>,
+    xml: Option<bool>,
+    csrf_token: Option<String>,
+    htmx_attributes: Option<Bound<'_, PyDict>>,
+    htmx_selectors: Option<Vec<String>>,
+    preserve_directives: Option<bool>,
+    strip_comments: Option<bool>,
+    keep_comments: Option<Vec<String>>,
+    collapse_whitespace: Option<bool>,
+    source_map: Option<bool>,
+) -> PyResult<Vec<HtmlTransformResult>> {
+    let config = config_from_py(
+        root_attributes,
+        all_attributes,
+        check_end_names,
         watch_on_attribute,
-    );
-
-    match set_html_attributes_rust(html, &config) {
-        Ok((html, captured)) => {
-            // Convert captured attributes to a Python dictionary
-            let captured_dict = PyDict::new(py);
-            for (id, attrs) in captured {
-                captured_dict.set_item(id, attrs)?;
+        source_map,
+        attribute_order,
+        repair_misnested,
+        xml,
+        csrf_token,
+        htmx_attributes,
+        htmx_selectors,
+        preserve_directives,
+        strip_comments,
+        keep_comments,
+        collapse_whitespace,
+    )?;
+
+    let mut timer = Timer::start("set_html_attributes_many");
+    // The transformation doesn't touch any Python objects, so other threads can run meanwhile
+    let results: Vec<BatchResult> = py
+        .detach(|| {
+            if parallel.unwrap_or(false) {
+                set_html_attributes_many_parallel_rust(&htmls, &config, num_threads)
+                    .map_err(|e| e.to_string())
+            } else {
+                Ok(set_html_attributes_many_rust(&htmls, &config))
             }
+        })
+        .map_err(PyValueError::new_err)?;
+    timer.phase("transform");
+
+    let mut output = Vec::with_capacity(results.len());
+    for (index, result) in results.into_iter().enumerate() {
+        let result = result.map_err(|e| {
+            let prefix = format!("Failed to transform HTML at index {index}: ");
+            html_error(py, &*e, &htmls[index], &prefix, None)
+        })?;
+
+        let prefix = format!("HTML at index {index}: ");
+        let warnings = warn_recovered(py, &result.warnings, &prefix, None)?;
+        let output_result = HtmlTransformResult::new(py, result, &htmls[index], warnings)?;
+        output.push(output_result);
+    }
+    timer.phase("to_python");
+    Ok(output)
+}
+
+/// Enable, resize or disable the in-memory LRU cache of `set_html_attributes()` results.
+///
+/// Production renders often transform the same HTML with the same attributes over and over.
+/// With the cache enabled, such repeated calls return the cached result instead.
+/// Results are keyed by a hash of the HTML and of all the other arguments, and keep the HTML
+/// to check that a cached result is for the same input.
+/// The cache is shared by all threads, and is disabled by default.
+///
+/// Args:
+///     maxsize (int): Maximum number of cached results. Least recently used results are evicted
+///         first. 0 disables the cache and drops all cached results.
+///
+/// Example:
+///     >>> configure_cache(1024)
+///     >>> html, captured = set_html_attributes('<div></div>', [], ['data-v-123'])
+///     >>> html, captured = set_html_attributes('<div></div>', [], ['data-v-123'])
+///     >>> cache_info()
+///     {'hits': 1, 'misses': 1, 'maxsize': 1024, 'currsize': 1, 'heap_size': ...}
+#[pyfunction]
+#[pyo3(signature = (maxsize))]
+pub fn configure_cache(maxsize: usize) {
+    HTML_CACHE.resize(maxsize);
+}
+
+/// Get the statistics of the cache of `set_html_attributes()` results.
+///
+/// Returns:
+///     Dict[str, int]: Dictionary with keys `hits`, `misses`, `maxsize` and `currsize`,
+///         same as `functools.lru_cache().cache_info()`. `maxsize` is 0 if the cache is disabled.
+///         `heap_size` is the approximate number of bytes taken by the cached results.
+#[pyfunction]
+pub fn cache_info(py: Python) -> PyResult<Py<PyAny>> {
+    let info = HTML_CACHE.info();
+    let info_dict = PyDict::new(py);
+    info_dict.set_item("hits", info.hits)?;
+    info_dict.set_item("misses", info.misses)?;
+    info_dict.set_item("maxsize", info.maxsize)?;
+    info_dict.set_item("currsize", info.currsize)?;
+    info_dict.set_item("heap_size", info.heap_size)?;
+    Ok(info_dict.into_any().unbind())
+}
+
+/// Remove all results from the cache of `set_html_attributes()` results and reset its statistics.
+#[pyfunction]
+pub fn cache_clear() {
+    HTML_CACHE.clear();
+}
+
+/// Fingerprint HTML, ignoring insignificant whitespace, e.g. to use as a cache key
+/// for transformed fragments.
+///
+/// Runs of whitespace in text and between attributes are collapsed. Attribute values, comments,
+/// and content of `<pre>`, `<script>`, `<style>` and `<textarea>` are hashed as is.
+///
+/// Args:
+///     html (str): The HTML string to hash.
+///
+/// Returns:
+///     str: 128-bit xxHash (XXH3) of the normalized HTML, as 32 hexadecimal characters.
+///
+/// Example:
+///     >>> hash_html('<div>\n  <p>Hi</p>\n</div>') == hash_html('<div> <p>Hi</p> </div>')
+///     True
+#[pyfunction]
+#[pyo3(signature = (html))]
+pub fn hash_html(html: &str) -> String {
+    format!("{:032x}", hash_html_rust(html))
+}
+
+/// Fingerprint Django template source, ignoring insignificant whitespace, e.g. to use
+/// as a cache key for compiled templates.
+///
+/// Same as `hash_html()`, but also ignores whitespace inside `{{ }}` and `{% %}` tags.
+/// String literals inside tags, `{# #}` comments and the content of `{% verbatim %}` blocks are hashed as is.
+///
+/// Args:
+///     source (str): The template source to hash.
+///
+/// Returns:
+///     str: 128-bit xxHash (XXH3) of the normalized source, as 32 hexadecimal characters.
+///
+/// Example:
+///     >>> hash_template('{{ name }}') == hash_template('{{name}}')
+///     True
+#[pyfunction]
+#[pyo3(signature = (source))]
+pub fn hash_template(source: &str) -> String {
+    format!("{:032x}", hash_template_rust(source))
+}
+
+/// Find the assets that the HTML references or contains, e.g. to build a manifest
+/// of the JS and CSS dependencies of components.
+///
+/// Recognizes `<script src>`, `<link href>`, `<img src>`, and inline `<script>` and `<style>` blocks.
+/// Content of `<script>` and `<style>` is not parsed as HTML.
+///
+/// Args:
+///     html (str): The HTML string to scan. Can be a fragment or full document.
+///
+/// Returns:
+///     List[Dict[str, Any]]: The assets in the order they appear in the HTML. Each has keys:
+///         - `kind` (str): One of `script`, `link`, `image`, `inline_script`, `inline_style`
+///         - `url` (str, optional): Value of the `src` or `href` attribute, `None` for inline assets
+///         - `rel` (str, optional): Value of the `rel` attribute of `<link>`, e.g. `stylesheet`
+///         - `content` (str, optional): Content of inline assets
+///         - `hash` (str, optional): 128-bit xxHash (XXH3) of the content of inline assets,
+///           as 32 hexadecimal characters
+///         - `start`, `end` (int): Position of the start tag
+///
+/// Example:
+///     >>> assets = extract_html_assets('<script src="app.js"></script><style>p { color: red }</style>')
+///     >>> [(a["kind"], a["url"]) for a in assets]
+///     [('script', 'app.js'), ('inline_style', None)]
+///
+/// Raises:
+///     HtmlTransformError: If the HTML is malformed or cannot be parsed. Subclass of `ValueError`.
+#[pyfunction]
+#[pyo3(signature = (html))]
+pub fn extract_html_assets(py: Python, html: &str) -> PyResult<Py<PyAny>> {
+    let mut timer = Timer::start("extract_html_assets");
+    let assets = extract_html_assets_rust(html).map_err(|e| html_error(py, &*e, html, "", None))?;
+    timer.phase("parse");
+
+    let mut offsets = CharOffsets::new(html);
+    let result = PyList::empty(py);
+    for asset in assets {
+        let asset_dict = PyDict::new(py);
+        asset_dict.set_item("kind", asset.kind.as_str())?;
+        asset_dict.set_item("url", asset.url)?;
+        asset_dict.set_item("rel", asset.rel)?;
+        asset_dict.set_item("content", asset.content)?;
+        asset_dict.set_item("hash", asset.hash.map(|hash| format!("{:032x}", hash)))?;
+        // Python strings are indexed by characters, not bytes
+        asset_dict.set_item("start", offsets.get(asset.start))?;
+        asset_dict.set_item("end", offsets.get(asset.end))?;
+        result.append(asset_dict)?;
+    }
+    timer.phase("to_python");
+    Ok(result.into_any().unbind())
+}
+
+/// Scope CSS to a component by appending an attribute selector to all selectors.
+///
+/// Selectors inside `@media`, `@supports`, `@container` and `@layer` blocks are scoped too.
+/// Keyframes, font faces and declarations are left untouched.
+///
+/// Args:
+///     css (str): The CSS string to transform.
+///     scope_attribute (str): Name of the attribute to scope the selectors with, e.g. `data-djc-c1a2`.
+///
+/// Returns:
+///     str: The CSS with scoped selectors.
+///
+/// Example:
+///     >>> scope_css('.btn > p:hover { color: red }', 'data-djc-c1a2')
+///     '.btn > p[data-djc-c1a2]:hover { color: red }'
+///
+/// Raises:
+///     ValueError: If the CSS is malformed, e.g. has unclosed blocks.
+#[pyfunction]
+#[pyo3(signature = (css, scope_attribute))]
+pub fn scope_css(css: &str, scope_attribute: String) -> PyResult<String> {
+    let config = CssScopeConfig::new(scope_attribute);
+
+    let mut timer = Timer::start("scope_css");
+    let result = scope_css_rust(css, &config).map_err(|e| PyValueError::new_err(e.to_string()));
+    timer.phase("transform");
+    result
+}
+
+/// Minify CSS by removing comments and insignificant whitespace, and by shortening numbers
+/// (e.g. `0px` -> `0`, `0.5em` -> `.5em`).
+///
+/// Comments starting with `/*!` (e.g. licenses) are kept.
+///
+/// Args:
+///     css (str): The CSS string to minify.
+///
+/// Returns:
+///     str: The minified CSS.
+///
+/// Example:
+///     >>> minify_css('.btn > p { margin: 0px 0.5em; }')
+///     '.btn>p{margin:0 .5em}'
+///
+/// Raises:
+///     ValueError: If the CSS is malformed, e.g. has unclosed strings or comments.
+#[pyfunction]
+#[pyo3(signature = (css))]
+pub fn minify_css(css: &str) -> PyResult<String> {
+    let mut timer = Timer::start("minify_css");
+    let result = minify_css_rust(css).map_err(|e| PyValueError::new_err(e.to_string()));
+    timer.phase("transform");
+    result
+}
+
+/// Find all modules that the JS imports or re-exports from.
+///
+/// Recognizes static imports (`import x from "spec"`), side-effect imports (`import "spec"`),
+/// dynamic imports with a literal specifier (`import("spec")`) and re-exports
+/// (`export { a } from "spec"`). Text inside strings, comments, template literals
+/// and regexes is ignored.
+///
+/// Args:
+///     js (str): The JavaScript source code.
+///
+/// Returns:
+///     List[Dict[str, Any]]: The dependencies in the order they appear in the source. Each has keys:
+///         - `specifier` (str): The module specifier, e.g. `./utils.js`
+///         - `kind` (str): One of `import`, `side_effect`, `dynamic`, `reexport`
+///         - `specifier_kind` (str): One of `bare`, `relative`, `absolute`, `url`.
+///           Only bare specifiers are resolved through an import map.
+///         - `names` (List[str]): Names imported from the module, `*` for namespace imports
+///         - `start`, `end` (int): Position of the whole statement
+///         - `specifier_start`, `specifier_end` (int): Position of the specifier string, including quotes
+///
+/// Example:
+///     >>> deps = extract_js_dependencies('import { a } from "lib";')
+///     >>> deps[0]["specifier"], deps[0]["specifier_kind"], deps[0]["names"]
+///     ('lib', 'bare', ['a'])
+///
+/// Raises:
+///     ValueError: If the JS cannot be tokenized, e.g. has unclosed strings or comments.
+#[pyfunction]
+#[pyo3(signature = (js))]
+pub fn extract_js_dependencies(py: Python, js: &str) -> PyResult<Py<PyAny>> {
+    let mut timer = Timer::start("extract_js_dependencies");
+    let dependencies =
+        extract_js_dependencies_rust(js).map_err(|e| PyValueError::new_err(e.to_string()))?;
+    timer.phase("parse");
+
+    let mut offsets = CharOffsets::new(js);
+    let result = PyList::empty(py);
+    for dep in dependencies {
+        let dep_dict = PyDict::new(py);
+        dep_dict.set_item("specifier", dep.specifier)?;
+        dep_dict.set_item("kind", dep.kind.as_str())?;
+        dep_dict.set_item("specifier_kind", dep.specifier_kind.as_str())?;
+        dep_dict.set_item("names", dep.names)?;
+        // Python strings are indexed by characters, not bytes
+        dep_dict.set_item("start", offsets.get(dep.start))?;
+        dep_dict.set_item("end", offsets.get(dep.end))?;
+        dep_dict.set_item("specifier_start", offsets.get(dep.specifier_start))?;
+        dep_dict.set_item("specifier_end", offsets.get(dep.specifier_end))?;
+        result.append(dep_dict)?;
+    }
+    timer.phase("to_python");
+    Ok(result.into_any().unbind())
+}
+
+/// Get the time spent in the functions of this module, to attribute render latency
+/// between Rust and Python.
+///
+/// Stats are only recorded after they are enabled with `enable_stats()`, or from the start
+/// if the `DJC_CORE_STATS` environment variable is set (to anything other than `0`).
+///
+/// Returns:
+///     Dict[str, Dict[str, Any]]: For each function that was called, a dictionary with keys:
+///         - `calls` (int): Number of calls, including the ones that raised an error
+///         - `phases` (Dict[str, float]): Total time in seconds spent in each phase, e.g. `transform`
+///           for the work done in Rust, and `to_python` for the conversion of the result to Python objects.
+///
+/// Example:
+///     >>> enable_stats()
+///     >>> html, captured = set_html_attributes('<div></div>', [], ['data-v-123'])
+///     >>> get_stats()
+///     {'set_html_attributes': {'calls': 1, 'phases': {'to_python': 1.2e-06, 'transform': 8.4e-06}}}
+#[pyfunction]
+pub fn get_stats(py: Python) -> PyResult<Py<PyAny>> {
+    Ok(stats::to_py(py)?.into_any().unbind())
+}
+
+/// Enable or disable recording of the stats returned by `get_stats()`.
+///
+/// Args:
+///     enabled (bool, optional): Whether to record the stats. Defaults to true.
+#[pyfunction]
+#[pyo3(signature = (enabled=true))]
+pub fn enable_stats(enabled: bool) {
+    stats::set_enabled(enabled);
+}
+
+/// Remove all recorded stats.
+#[pyfunction]
+pub fn reset_stats() {
+    stats::reset();
+}
 
-            // Convert items to Bound<PyAny> for the tuple
-            use pyo3::types::PyString;
-            let html_obj = PyString::new(py, &html).as_any().clone();
-            let dict_obj = captured_dict.as_any().clone();
-            let result = PyTuple::new(py, vec![html_obj, dict_obj])?;
-            Ok(result.into_any().unbind())
+/// Forward the diagnostic events of the Rust code (e.g. where parsing failed, or how long
+/// the output was) to the Python logger `djc_core`.
+///
+/// Events are forwarded as log records with the corresponding level. Rust's `TRACE` events
+/// are logged with level 5, below `logging.DEBUG`. The message is prefixed with the name of
+/// the operation, e.g. `transform_html: Failed to parse HTML position=12 error=...`.
+///
+/// Args:
+///     enabled (bool, optional): Whether to forward the events. Defaults to true.
+///     level (int, optional): Minimum level of the forwarded events. Defaults to `logging.DEBUG`.
+///
+/// Example:
+///     >>> import logging
+///     >>> logging.basicConfig(level=logging.DEBUG)
+///     >>> enable_logging()
+///     >>> html, captured = set_html_attributes('<div></div>', [], ['data-v-123'])
+///     DEBUG:djc_core:transform_html: Transformed HTML output_len=25 captured=0 warnings=0
+///
+/// Raises:
+///     RuntimeError: If another library already installed a global `tracing` subscriber. Raised by
+///         every call that enables the logging, never when disabling it.
+#[pyfunction]
+#[pyo3(signature = (enabled=true, level=10))]
+pub fn enable_logging(enabled: bool, level: u32) -> PyResult<()> {
+    let level = level.min(u8::MAX as u32) as u8;
+    logging::set_level(enabled.then_some(level))
+}
+
+/// Get the version of the package and the optional features it was built with,
+/// so callers can detect what's supported instead of pinning exact versions.
+///
+/// The keyword arguments of all the functions can be inspected with `inspect.signature()`.
+///
+/// Returns:
+///     Dict[str, Any]: Dictionary with keys:
+///         - `version` (str): Version of the package, e.g. `1.1.0`
+///         - `features` (Dict[str, bool]): Whether each of the optional features is enabled:
+///             - `cache`: The LRU cache of `set_html_attributes()` results, see `configure_cache()`
+///             - `parallel`: Parallel batches with `set_html_attributes_many(parallel=True)`
+///             - `wasm`: Whether it's the WebAssembly build, always `False` for Python
+///         - `functions` (List[str]): Names of all functions of the module, in alphabetical order
+///         - `options` (List[str]): Supported options of the HTML transformer, i.e. the keyword arguments
+///           of `set_html_attributes()`, `set_html_attributes_many()` and `transform_html_file()`
+///
+/// Example:
+///     >>> features()["features"]
+///     {'cache': True, 'parallel': True, 'wasm': False}
+///     >>> "collapse_whitespace" in features()["options"]
+///     True
+#[pyfunction]
+#[pyo3(pass_module)]
+pub fn features<'py>(module: &Bound<'py, PyModule>) -> PyResult<Bound<'py, PyDict>> {
+    let py = module.py();
+    let enabled = enabled_features();
+    let features_dict = PyDict::new(py);
+    features_dict.set_item("cache", enabled.contains(&"cache"))?;
+    features_dict.set_item("parallel", enabled.contains(&"parallel"))?;
+    features_dict.set_item("wasm", cfg!(target_family = "wasm"))?;
+
+    let mut functions = Vec::new();
+    for (name, value) in module.dict() {
+        if value.is_callable() && !value.is_instance_of::<pyo3::types::PyType>() {
+            functions.push(name.extract::<String>()?);
+        }
+    }
+    functions.sort();
+
+    let result = PyDict::new(py);
+    result.set_item("version", env!("CARGO_PKG_VERSION"))?;
+    result.set_item("features", features_dict)?;
+    result.set_item("functions", functions)?;
+    result.set_item("options", HTML_TRANSFORMER_OPTIONS)?;
+    Ok(result)
+}
+
+/// Config from the arguments that are shared by the HTML transformer functions
+#[allow(clippy::too_many_arguments)]
+fn config_from_py(
+    root_attributes: Vec<String>,
+    all_attributes: Vec<String>,
+    check_end_names: Option<bool>,
+    watch_on_attribute: Option<String>,
+    source_map: Option<bool>,
+    attribute_order: Option<AttributeOrderArg>,
+    repair_misnested: Option<bool>,
+    xml: Option<bool>,
+    csrf_token: Option<String>,
+    htmx_attributes: Option<Bound<'_, PyDict>>,
+    htmx_selectors: Option<Vec<String>>,
+    preserve_directives: Option<bool>,
+    strip_comments: Option<bool>,
+    keep_comments: Option<Vec<String>>,
+    collapse_whitespace: Option<bool>,
+) -> PyResult<HtmlTransformerConfig> {
+    let mut config = HtmlTransformerConfig::new(
+        root_attributes,
+        all_attributes,
+        check_end_names.unwrap_or(false),
+        watch_on_attribute,
+    )
+    .with_source_map(source_map.unwrap_or(false))
+    .with_attribute_order(attribute_order_from_py(attribute_order)?)
+    .with_repair_misnested(repair_misnested.unwrap_or(false))
+    .with_xml(xml.unwrap_or(false))
+    .with_csrf_token(csrf_token)
+    .with_htmx(htmx_from_py(htmx_attributes, htmx_selectors)?)
+    .with_preserve_directives(preserve_directives.unwrap_or(false))
+    .with_strip_comments(strip_comments.unwrap_or(false))
+    .with_collapse_whitespace(collapse_whitespace.unwrap_or(false));
+    if let Some(patterns) = keep_comments {
+        config = config.with_kept_comments(patterns);
+    }
+    Ok(config)
+}
+
+/// Value of the `attribute_order` argument of the HTML transformer functions
+#[derive(FromPyObject)]
+pub enum AttributeOrderArg {
+    Name(String),
+    Priority(Vec<String>),
+}
+
+fn attribute_order_from_py(arg: Option<AttributeOrderArg>) -> PyResult<AttributeOrder> {
+    match arg {
+        None => Ok(AttributeOrder::Original),
+        Some(AttributeOrderArg::Name(name)) => match name.as_str() {
+            "original" => Ok(AttributeOrder::Original),
+            "alphabetical" => Ok(AttributeOrder::Alphabetical),
+            _ => Err(PyValueError::new_err(format!(
+                "attribute_order must be 'original', 'alphabetical' or a list of attribute names, got '{}'",
+                name
+            ))),
+        },
+        Some(AttributeOrderArg::Priority(names)) => Ok(AttributeOrder::Priority(names)),
+    }
+}
+
+/// HTMX attributes from the `htmx_attributes` and `htmx_selectors` arguments. Values that
+/// are not strings are serialized as JSON.
+fn htmx_from_py(
+    attributes: Option<Bound<'_, PyDict>>,
+    selectors: Option<Vec<String>>,
+) -> PyResult<Option<HtmxAttributes>> {
+    let Some(attributes) = attributes else {
+        return Ok(None);
+    };
+    let py = attributes.py();
+    let mut pairs = Vec::with_capacity(attributes.len());
+    for (name, value) in attributes.iter() {
+        let value = match value.extract::<String>() {
+            Ok(value) => value,
+            Err(_) => py
+                .import("json")?
+                .call_method1("dumps", (value,))?
+                .extract()?,
+        };
+        pairs.push((name.extract()?, value));
+    }
+    let htmx = HtmxAttributes::new(pairs, &selectors.unwrap_or_default())
+        .map_err(|e| PyValueError::new_err(e.to_string()))?;
+    Ok(Some(htmx))
+}
+
+/// Issue a `DjcHtmlRecoveryWarning` for each of the problems that were worked around.
+/// `prefix` says where the HTML came from, e.g. its position in a batch, and `filename`
+/// is the file it was read from, if any.
+///
+/// Returns the issued warnings. Raises the warning if the warnings filter turns it into an error.
+fn warn_recovered<'py>(
+    py: Python<'py>,
+    warnings: &[TransformWarning],
+    prefix: &str,
+    filename: Option<&Path>,
+) -> PyResult<Vec<Bound<'py, PyAny>>> {
+    let mut issued = Vec::with_capacity(warnings.len());
+    if warnings.is_empty() {
+        return Ok(issued);
+    }
+    let warn = py.import("warnings")?.getattr("warn")?;
+    for warning in warnings {
+        let message = format!("{}{}", prefix, warning);
+        let warning_obj = py.get_type::<DjcHtmlRecoveryWarning>().call1((message,))?;
+        warning_obj.setattr("filename", filename.map(Path::to_string_lossy))?;
+        warning_obj.setattr("line", warning.line)?;
+        warning_obj.setattr("column", warning.column)?;
+        // Point to the caller of the djc_core function
+        warn.call1((&warning_obj, py.None(), 1))?;
+        issued.push(warning_obj);
+    }
+    Ok(issued)
+}
+
+/// Convert an error of the HTML transformer to `HtmlTransformError` with the position
+/// of the problem in `html`, and the file it was read from, if any. Other errors are
+/// raised as `ValueError`.
+fn html_error(
+    py: Python,
+    error: &(dyn std::error::Error + 'static),
+    html: &str,
+    prefix: &str,
+    filename: Option<&Path>,
+) -> PyErr {
+    let message = format!("{}{}", prefix, error);
+    let Some(error) = error.downcast_ref::<TransformError>() else {
+        return PyValueError::new_err(message);
+    };
+
+    let err = HtmlTransformError::new_err(message);
+    let set_position = || -> PyResult<()> {
+        let value = err.value(py);
+        value.setattr("filename", filename.map(Path::to_string_lossy))?;
+        value.setattr("line", error.line)?;
+        value.setattr("column", error.column)?;
+        // Python strings are indexed by characters, not bytes
+        value.setattr("offset", CharOffsets::new(html).get(error.offset))?;
+        value.setattr("source_snippet", &error.snippet)?;
+        Ok(())
+    };
+    match set_position() {
+        Ok(()) => err,
+        Err(e) => e,
+    }
+}
+
+/// Converts byte offsets into the string to character offsets, as used by Python strings.
+///
+/// Offsets are counted from the previously converted one, so converting offsets
+/// in increasing order is linear in the length of the string.
+pub(crate) struct CharOffsets<'a> {
+    text: &'a str,
+    is_ascii: bool,
+    last_byte: usize,
+    last_char: usize,
+}
+
+impl<'a> CharOffsets<'a> {
+    pub(crate) fn new(text: &'a str) -> Self {
+        CharOffsets {
+            text,
+            is_ascii: text.is_ascii(),
+            last_byte: 0,
+            last_char: 0,
+        }
+    }
+
+    pub(crate) fn get(&mut self, byte_offset: usize) -> usize {
+        if self.is_ascii {
+            return byte_offset;
+        }
+        if byte_offset < self.last_byte {
+            self.last_byte = 0;
+            self.last_char = 0;
         }
-        Err(e) => Err(PyValueError::new_err(e.to_string())),
+        self.last_char += self.text[self.last_byte..byte_offset].chars().count();
+        self.last_byte = byte_offset;
+        self.last_char
     }
 }
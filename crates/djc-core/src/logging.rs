@@ -0,0 +1,126 @@
+use pyo3::prelude::*;
+use pyo3::sync::PyOnceLock;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::OnceLock;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Metadata, Subscriber};
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+
+// Name of the Python logger that the Rust events are forwarded to
+const LOGGER_NAME: &str = "djc_core";
+
+// Most verbose level that is forwarded, as Python logging level. 0 means disabled.
+static MAX_LEVEL: AtomicU8 = AtomicU8::new(0);
+// Whether the subscriber was installed, set on the first call that enables forwarding
+static INSTALLED: OnceLock<bool> = OnceLock::new();
+static LOGGER: PyOnceLock<Py<PyAny>> = PyOnceLock::new();
+
+/// Python logging level of the tracing level. TRACE is below Python's DEBUG (10).
+fn python_level(level: &Level) -> u8 {
+    match *level {
+        Level::ERROR => 40,
+        Level::WARN => 30,
+        Level::INFO => 20,
+        Level::DEBUG => 10,
+        Level::TRACE => 5,
+    }
+}
+
+/// Start forwarding the Rust events of at least `level` (Python logging level) to Python logging.
+/// Pass `None` to stop forwarding.
+pub fn set_level(level: Option<u8>) -> PyResult<()> {
+    let Some(level) = level else {
+        MAX_LEVEL.store(0, Ordering::Relaxed);
+        return Ok(());
+    };
+
+    let installed = *INSTALLED.get_or_init(|| {
+        let subscriber = tracing_subscriber::registry().with(PythonLoggingLayer);
+        tracing::subscriber::set_global_default(subscriber).is_ok()
+    });
+    if !installed {
+        return Err(pyo3::exceptions::PyRuntimeError::new_err(
+            "Another tracing subscriber is already installed",
+        ));
+    }
+    // Forwarding everything would be indistinguishable from disabled
+    MAX_LEVEL.store(level.max(1), Ordering::Relaxed);
+    Ok(())
+}
+
+/// Forwards tracing events to the Python logger, prefixed with the names of the spans
+/// they happened in, e.g. `transform_html: Failed to parse HTML position=12 error=...`.
+struct PythonLoggingLayer;
+
+impl<S> Layer<S> for PythonLoggingLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn register_callsite(
+        &self,
+        _metadata: &'static Metadata<'static>,
+    ) -> tracing::subscriber::Interest {
+        // The level can change at runtime, so decide on each event in `enabled()`
+        tracing::subscriber::Interest::sometimes()
+    }
+
+    fn enabled(&self, metadata: &Metadata<'_>, _ctx: Context<'_, S>) -> bool {
+        let max_level = MAX_LEVEL.load(Ordering::Relaxed);
+        max_level != 0 && python_level(metadata.level()) >= max_level
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let mut message = String::new();
+        if let Some(scope) = ctx.event_scope(event) {
+            for span in scope.from_root() {
+                message.push_str(span.name());
+                message.push_str(": ");
+            }
+        }
+        let mut visitor = MessageVisitor {
+            message: &mut message,
+            fields: String::new(),
+        };
+        event.record(&mut visitor);
+        let fields = visitor.fields;
+        message.push_str(&fields);
+
+        let level = python_level(event.metadata().level());
+        Python::attach(|py| {
+            let logger = LOGGER.get_or_try_init(py, || {
+                let logging = py.import("logging")?;
+                Ok::<_, PyErr>(logging.call_method1("getLogger", (LOGGER_NAME,))?.unbind())
+            });
+            // Errors in logging must not break the transformation
+            if let Ok(logger) = logger {
+                let _ = logger.call_method1(py, "log", (level, "%s", message));
+            }
+        });
+    }
+}
+
+/// Formats the event as `message key=value key=value`
+struct MessageVisitor<'a> {
+    message: &'a mut String,
+    fields: String,
+}
+
+impl Visit for MessageVisitor<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message.push_str(value);
+        } else {
+            let _ = write!(self.fields, " {}={}", field.name(), value);
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{:?}", value);
+        } else {
+            let _ = write!(self.fields, " {}={:?}", field.name(), value);
+        }
+    }
+}
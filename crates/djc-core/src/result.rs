@@ -0,0 +1,221 @@
+use djc_html_transformer::{CapturedAttributes, TransformResult};
+use pyo3::prelude::*;
+use pyo3::pyclass::CompareOp;
+use pyo3::sync::PyOnceLock;
+use pyo3::types::{PyDict, PyList, PyString, PyTuple};
+use std::sync::Arc;
+
+use crate::CharOffsets;
+
+/// Result of `set_html_attributes()`, `set_html_attributes_many()` and `transform_html_file()`.
+///
+/// For backwards compatibility, the result also behaves like the `(html, captured)` tuple
+/// that was returned before, or `(html, captured, source_map)` if the source map was requested.
+/// So `html, captured = set_html_attributes(...)` still works.
+#[pyclass(module = "djc_core", name = "HtmlTransformResult", frozen)]
+pub struct HtmlTransformResult {
+    html: Py<PyString>,
+    // Converted to a dict only when accessed, as there can be thousands of captured elements
+    captured: Arc<CapturedAttributes>,
+    captured_dict: PyOnceLock<Py<PyDict>>,
+    source_map: Option<Py<PyDict>>,
+    roots: Py<PyList>,
+    stats: Py<PyDict>,
+    warnings: Py<PyList>,
+}
+
+impl HtmlTransformResult {
+    /// Convert the result of transforming `source`. `warnings` are the already issued
+    /// `DjcHtmlRecoveryWarning` instances.
+    pub fn new(
+        py: Python,
+        result: TransformResult,
+        source: &str,
+        warnings: Vec<Bound<'_, PyAny>>,
+    ) -> PyResult<Self> {
+        let source_map = match result.source_map {
+            Some(source_map) => {
+                // Python strings are indexed by characters, not bytes
+                let mut output_offsets = CharOffsets::new(&result.html);
+                let mut source_offsets = CharOffsets::new(source);
+                let mappings = PyList::empty(py);
+                for m in source_map.mappings {
+                    let mapping = [
+                        output_offsets.get(m.output_start),
+                        output_offsets.get(m.output_end),
+                        source_offsets.get(m.source_start),
+                        source_offsets.get(m.source_end),
+                    ];
+                    mappings.append(PyList::new(py, mapping)?)?;
+                }
+                let source_map_dict = PyDict::new(py);
+                source_map_dict.set_item("version", 1)?;
+                source_map_dict.set_item("mappings", mappings)?;
+                Some(source_map_dict.unbind())
+            }
+            None => None,
+        };
+
+        let stats = PyDict::new(py);
+        stats.set_item("elements", result.stats.elements)?;
+        stats.set_item("attributes_added", result.stats.attributes_added)?;
+
+        Ok(HtmlTransformResult {
+            html: PyString::new(py, &result.html).unbind(),
+            captured: Arc::new(result.captured),
+            captured_dict: PyOnceLock::new(),
+            source_map,
+            roots: PyList::new(py, result.roots)?.unbind(),
+            stats: stats.unbind(),
+            warnings: PyList::new(py, warnings)?.unbind(),
+        })
+    }
+
+    /// The tuple that was returned before this class was introduced
+    fn as_tuple<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyTuple>> {
+        let mut items = vec![
+            self.html.bind(py).as_any().clone(),
+            self.captured_dict(py)?.bind(py).as_any().clone(),
+        ];
+        if let Some(source_map) = &self.source_map {
+            items.push(source_map.bind(py).as_any().clone());
+        }
+        PyTuple::new(py, items)
+    }
+
+    fn captured_dict(&self, py: Python) -> PyResult<&Py<PyDict>> {
+        self.captured_dict
+            .get_or_try_init(py, || Ok(captured_to_py(py, &self.captured)?.unbind()))
+    }
+}
+
+#[pymethods]
+impl HtmlTransformResult {
+    /// The transformed HTML
+    #[getter]
+    fn html(&self, py: Python) -> Py<PyString> {
+        self.html.clone_ref(py)
+    }
+
+    /// Captured attribute values mapped to the attributes that were added to those elements.
+    /// Empty if `watch_on_attribute` wasn't set.
+    #[getter]
+    fn captured(&self, py: Python) -> PyResult<Py<PyDict>> {
+        Ok(self.captured_dict(py)?.clone_ref(py))
+    }
+
+    /// Iterate over the `(value, attributes)` pairs of the captured elements, in the order
+    /// of appearance, including elements with the same value. Unlike `captured`, this doesn't
+    /// create all the Python objects at once.
+    fn iter_captured(&self) -> CapturedIterator {
+        CapturedIterator {
+            captured: Arc::clone(&self.captured),
+            index: 0,
+        }
+    }
+
+    /// Mapping of the output back to the input, `None` unless requested with `source_map=True`
+    #[getter]
+    fn source_map(&self, py: Python) -> Option<Py<PyDict>> {
+        self.source_map
+            .as_ref()
+            .map(|source_map| source_map.clone_ref(py))
+    }
+
+    /// Tag names of the root elements, in the order of appearance. Lowercased, except with `xml=True`,
+    /// where the names are case-sensitive and kept as they are.
+    #[getter]
+    fn roots(&self, py: Python) -> Py<PyList> {
+        self.roots.clone_ref(py)
+    }
+
+    /// Number of `elements`, and number of attributes added to them (`attributes_added`)
+    #[getter]
+    fn stats(&self, py: Python) -> Py<PyDict> {
+        self.stats.clone_ref(py)
+    }
+
+    /// `DjcHtmlRecoveryWarning` instances for the problems that were worked around
+    #[getter]
+    fn warnings(&self, py: Python) -> Py<PyList> {
+        self.warnings.clone_ref(py)
+    }
+
+    fn __len__(&self) -> usize {
+        if self.source_map.is_some() {
+            3
+        } else {
+            2
+        }
+    }
+
+    fn __getitem__<'py>(
+        &self,
+        py: Python<'py>,
+        index: &Bound<'py, PyAny>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        self.as_tuple(py)?.as_any().get_item(index)
+    }
+
+    fn __iter__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        Ok(self.as_tuple(py)?.as_any().try_iter()?.into_any())
+    }
+
+    /// Equal to another result, or to the tuple, with the same HTML, captured attributes
+    /// and source map
+    fn __eq__<'py>(&self, py: Python<'py>, other: &Bound<'py, PyAny>) -> PyResult<Py<PyAny>> {
+        let other = if let Ok(other) = other.cast::<HtmlTransformResult>() {
+            other.get().as_tuple(py)?
+        } else if let Ok(other) = other.cast::<PyTuple>() {
+            other.clone()
+        } else {
+            return Ok(py.NotImplemented());
+        };
+        let equal = self
+            .as_tuple(py)?
+            .as_any()
+            .rich_compare(other, CompareOp::Eq)?;
+        Ok(equal.unbind())
+    }
+
+    fn __repr__(&self, py: Python) -> PyResult<String> {
+        Ok(format!(
+            "HtmlTransformResult(html={}, captured={}, roots={})",
+            self.html.bind(py).repr()?,
+            self.captured_dict(py)?.bind(py).repr()?,
+            self.roots.bind(py).repr()?,
+        ))
+    }
+}
+
+/// Convert captured attributes to a Python dictionary
+fn captured_to_py<'py>(
+    py: Python<'py>,
+    captured: &CapturedAttributes,
+) -> PyResult<Bound<'py, PyDict>> {
+    let captured_dict = PyDict::new(py);
+    for (id, attrs) in captured {
+        captured_dict.set_item(id, attrs)?;
+    }
+    Ok(captured_dict)
+}
+
+/// Iterator returned by `HtmlTransformResult.iter_captured()`
+#[pyclass(module = "djc_core")]
+pub struct CapturedIterator {
+    captured: Arc<CapturedAttributes>,
+    index: usize,
+}
+
+#[pymethods]
+impl CapturedIterator {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&mut self) -> Option<(String, Vec<String>)> {
+        let item = self.captured.get(self.index)?.clone();
+        self.index += 1;
+        Some(item)
+    }
+}
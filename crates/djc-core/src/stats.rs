@@ -0,0 +1,98 @@
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+// Set the environment variable to any value other than `0` to record stats from the start
+const STATS_ENV_VAR: &str = "DJC_CORE_STATS";
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+// Function name -> stats. Ordered so that `get_stats()` is stable.
+static STATS: Mutex<BTreeMap<&'static str, FunctionStats>> = Mutex::new(BTreeMap::new());
+
+#[derive(Clone, Default)]
+struct FunctionStats {
+    calls: u64,
+    // Phase name -> total time spent in the phase
+    phases: BTreeMap<&'static str, Duration>,
+}
+
+/// Enable the stats if requested by the environment variable
+pub fn init_from_env() {
+    if std::env::var_os(STATS_ENV_VAR).is_some_and(|value| !value.is_empty() && value != "0") {
+        ENABLED.store(true, Ordering::Relaxed);
+    }
+}
+
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+pub fn reset() {
+    STATS.lock().unwrap_or_else(|e| e.into_inner()).clear();
+}
+
+/// Measures how long the phases of a single call take.
+///
+/// Does nothing if the stats are disabled. The timings are recorded when the timer is dropped,
+/// so also calls that raise an error are counted.
+pub struct Timer {
+    function: &'static str,
+    // `None` if the stats are disabled
+    last: Option<Instant>,
+    phases: Vec<(&'static str, Duration)>,
+}
+
+impl Timer {
+    pub fn start(function: &'static str) -> Self {
+        let enabled = ENABLED.load(Ordering::Relaxed);
+        Timer {
+            function,
+            last: enabled.then(Instant::now),
+            phases: Vec::new(),
+        }
+    }
+
+    /// Mark the end of the phase that started at the end of the previous phase
+    pub fn phase(&mut self, name: &'static str) {
+        if let Some(last) = self.last {
+            let now = Instant::now();
+            self.phases.push((name, now - last));
+            self.last = Some(now);
+        }
+    }
+}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        if self.last.is_none() {
+            return;
+        }
+        let mut stats = STATS.lock().unwrap_or_else(|e| e.into_inner());
+        let function_stats = stats.entry(self.function).or_default();
+        function_stats.calls += 1;
+        for (name, duration) in self.phases.drain(..) {
+            *function_stats.phases.entry(name).or_default() += duration;
+        }
+    }
+}
+
+/// Convert the stats to a Python dictionary, with times in seconds
+pub fn to_py<'py>(py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+    // Copy the stats so the lock is not held while creating Python objects
+    let stats = STATS.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    let stats_dict = PyDict::new(py);
+    for (function, function_stats) in &stats {
+        let phases_dict = PyDict::new(py);
+        for (name, duration) in &function_stats.phases {
+            phases_dict.set_item(name, duration.as_secs_f64())?;
+        }
+        let function_dict = PyDict::new(py);
+        function_dict.set_item("calls", function_stats.calls)?;
+        function_dict.set_item("phases", phases_dict)?;
+        stats_dict.set_item(function, function_dict)?;
+    }
+    Ok(stats_dict)
+}
@@ -0,0 +1,40 @@
+use minifier::minify;
+use scoper::scope;
+
+pub mod minifier;
+pub mod scoper;
+
+// Re-export the types that users need
+pub use scoper::{CssError, CssScopeConfig};
+
+/// Scope CSS to a component by appending an attribute selector to all selectors.
+///
+/// E.g. with `scope_attribute = "data-djc-c1a2"`, `.btn > p` becomes `.btn > p[data-djc-c1a2]`.
+///
+/// Args:
+///     css: The CSS string to transform.
+///     config: The CSS scoping configuration.
+///
+/// Returns:
+///     A Result containing either:
+///     - Ok(css): The CSS with scoped selectors
+///     - Err(error): An error if the CSS is malformed, e.g. has unclosed blocks.
+pub fn scope_css(css: &str, config: &CssScopeConfig) -> Result<String, Box<dyn std::error::Error>> {
+    Ok(scope(config, css)?)
+}
+
+/// Minify CSS by removing comments and insignificant whitespace, and by shortening numbers
+/// (e.g. `0px` -> `0`, `0.5em` -> `.5em`).
+///
+/// Comments starting with `/*!` (e.g. licenses) are kept.
+///
+/// Args:
+///     css: The CSS string to minify.
+///
+/// Returns:
+///     A Result containing either:
+///     - Ok(css): The minified CSS
+///     - Err(error): An error if the CSS is malformed, e.g. has unclosed strings or comments.
+pub fn minify_css(css: &str) -> Result<String, Box<dyn std::error::Error>> {
+    Ok(minify(css)?)
+}
@@ -0,0 +1,422 @@
+use crate::scoper::{comment_end, string_end, CssError};
+
+// Units for which `0<unit>` can be safely written as `0`. Other units like `s` or `%`
+// must be kept, as e.g. `transition: 0` or `flex-basis: 0` mean something else or are invalid.
+const LENGTH_UNITS: [&str; 15] = [
+    "px", "em", "rem", "ex", "ch", "vw", "vh", "vmin", "vmax", "cm", "mm", "q", "in", "pt", "pc",
+];
+
+// Characters that end a "word" (identifier, number, selector part, etc.)
+const DELIMITERS: &[u8] = b"{};,:()>+~!\"'";
+
+/// Collapses whitespace and strips comments while copying the CSS to the output.
+///
+/// Whitespace is only ever removed next to characters where it is not significant
+/// (e.g. `{`, `;`, `,`). Elsewhere, runs of whitespace are collapsed to a single space,
+/// because e.g. in `.a .b` the whitespace is a descendant combinator.
+struct Minifier<'a> {
+    css: &'a str,
+    out: String,
+    // Whether we skipped whitespace since the last emitted token
+    pending_space: bool,
+    // Whether the last emitted token allows to drop whitespace after it
+    last_strips_after: bool,
+    // Depth of parentheses, e.g. inside `calc()` or `:is()`
+    depth: i32,
+    // Whether we removed a comment since the last emitted token
+    removed_comment: bool,
+    // Whether the last emitted token is the name of a custom property, e.g. `--gap`
+    after_custom_property: bool,
+    // Whether we are in the value of a custom property, which is kept as it is written
+    in_custom_property: bool,
+}
+
+impl<'a> Minifier<'a> {
+    fn emit(&mut self, text: &str, strips_before: bool, strips_after: bool) {
+        if self.pending_space && !self.out.is_empty() && !self.last_strips_after && !strips_before
+        {
+            self.out.push(' ');
+        } else if self.removed_comment
+            && self.out.chars().next_back().is_some_and(is_word_char)
+            && text.chars().next().is_some_and(is_word_char)
+        {
+            // Comment between two words, e.g. `1px/**/2px`, separates them like whitespace
+            self.out.push(' ');
+        }
+        self.out.push_str(text);
+        self.pending_space = false;
+        self.removed_comment = false;
+        self.after_custom_property = false;
+        self.last_strips_after = strips_after;
+    }
+
+    fn emit_punctuation(&mut self, c: u8) {
+        match c {
+            b':' if self.after_custom_property => self.in_custom_property = true,
+            b'{' | b'}' | b';' => self.in_custom_property = false,
+            _ => {}
+        }
+        match c {
+            b'}' => {
+                // Last semicolon in a block is optional
+                if self.out.ends_with(';') {
+                    self.out.pop();
+                }
+                self.emit("}", true, true);
+            }
+            b';' if self.out.ends_with(';') || self.out.ends_with('{') => {
+                self.pending_space = false;
+            }
+            b'{' | b';' | b',' => self.emit(char_str(c), true, true),
+            b':' => self.emit(":", false, true),
+            b'!' => self.emit("!", true, true),
+            b'(' => {
+                self.depth += 1;
+                self.emit("(", false, true);
+            }
+            b')' => {
+                self.depth -= 1;
+                self.emit(")", true, false);
+            }
+            // Combinators. Inside parentheses, `+` may be part of `calc()`, where spaces are required.
+            b'>' | b'+' | b'~' => {
+                let is_combinator = self.depth <= 0;
+                self.emit(char_str(c), is_combinator, is_combinator);
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn run(&mut self) -> Result<(), CssError> {
+        let bytes = self.css.as_bytes();
+        let mut i = 0;
+        while i < bytes.len() {
+            let b = bytes[i];
+            if b.is_ascii_whitespace() {
+                self.pending_space = true;
+                i += 1;
+            } else if b == b'/' && bytes.get(i + 1) == Some(&b'*') {
+                let end = comment_end(self.css, i)?;
+                // Keep "important" comments like `/*! License ... */`
+                if bytes.get(i + 2) == Some(&b'!') {
+                    self.emit(&self.css[i..end], false, true);
+                } else {
+                    self.removed_comment = true;
+                }
+                i = end;
+            } else if b == b'"' || b == b'\'' {
+                let end = string_end(self.css, i)?;
+                self.emit(&self.css[i..end], false, false);
+                i = end;
+            } else if DELIMITERS.contains(&b) {
+                // Whitespace before `:` is significant only in selectors, e.g. `a :hover`
+                if b == b':' && self.pending_space && !self.starts_block(i)? {
+                    self.pending_space = false;
+                }
+                self.emit_punctuation(b);
+                i += 1;
+            } else if bytes[i..]
+                .get(..4)
+                .is_some_and(|prefix| prefix.eq_ignore_ascii_case(b"url("))
+            {
+                let end = self.url_end(i)?;
+                self.emit(&self.css[i..end], false, false);
+                i = end;
+            } else {
+                let mut end = i;
+                while end < bytes.len()
+                    && !bytes[end].is_ascii_whitespace()
+                    && !DELIMITERS.contains(&bytes[end])
+                    && (bytes[end] != b'/' || bytes.get(end + 1) != Some(&b'*'))
+                {
+                    // Escaped characters are part of the word, e.g. `.md\:flex`
+                    if bytes[end] == b'\\' {
+                        end += 1;
+                        if let Some(c) = self.css[end..].chars().next() {
+                            end += c.len_utf8();
+                        }
+                    } else {
+                        end += 1;
+                    }
+                }
+                let word = &self.css[i..end];
+                if self.in_custom_property || self.after_unicode_range_prefix() {
+                    self.emit(word, false, false);
+                } else {
+                    self.emit(&minify_number(word, self.depth), false, false);
+                }
+                self.after_custom_property = self.depth <= 0 && word.starts_with("--");
+                i = end;
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether the statement at `pos` is followed by a block, i.e. it's a selector or
+    /// an at-rule prelude rather than a declaration
+    fn starts_block(&self, pos: usize) -> Result<bool, CssError> {
+        let bytes = self.css.as_bytes();
+        let mut i = pos;
+        while i < bytes.len() {
+            match bytes[i] {
+                b'"' | b'\'' => i = string_end(self.css, i)?,
+                b'/' if bytes.get(i + 1) == Some(&b'*') => i = comment_end(self.css, i)?,
+                b'{' => return Ok(true),
+                b';' | b'}' => return Ok(false),
+                _ => i += 1,
+            }
+        }
+        Ok(false)
+    }
+
+    /// Whether the output ends with `U+` of a unicode range, e.g. `U+0025-00FF`,
+    /// whose hex digits must not be minified as numbers
+    fn after_unicode_range_prefix(&self) -> bool {
+        let Some(rest) = self
+            .out
+            .strip_suffix("U+")
+            .or_else(|| self.out.strip_suffix("u+"))
+        else {
+            return false;
+        };
+        !rest.chars().next_back().is_some_and(is_word_char)
+    }
+
+    /// Return the end of `url(...)` starting at `start`. Unquoted URLs may contain
+    /// characters like `;` or `//`, so we copy them as a whole.
+    fn url_end(&self, start: usize) -> Result<usize, CssError> {
+        let bytes = self.css.as_bytes();
+        let mut i = start + 4;
+        while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+            i += 1;
+        }
+        if i < bytes.len() && (bytes[i] == b'"' || bytes[i] == b'\'') {
+            i = string_end(self.css, i)?;
+        }
+        match self.css[i..].find(')') {
+            Some(idx) => Ok(i + idx + 1),
+            None => Err(CssError {
+                message: "Unclosed url()".to_string(),
+                offset: start,
+            }),
+        }
+    }
+}
+
+fn char_str(c: u8) -> &'static str {
+    match c {
+        b'{' => "{",
+        b';' => ";",
+        b',' => ",",
+        b'>' => ">",
+        b'+' => "+",
+        b'~' => "~",
+        _ => unreachable!(),
+    }
+}
+
+/// Shorten numbers, e.g. `0px` -> `0`, `0.50em` -> `.5em`, `1.0` -> `1`.
+/// Words that are not numbers are returned as is.
+fn minify_number(word: &str, depth: i32) -> String {
+    let (sign, rest) = match word.as_bytes().first() {
+        Some(b'-') | Some(b'+') => word.split_at(1),
+        _ => ("", word),
+    };
+    let int_len = rest.bytes().take_while(|b| b.is_ascii_digit()).count();
+    let (int_part, rest) = rest.split_at(int_len);
+    let (frac_part, unit) = match rest.strip_prefix('.') {
+        Some(after_dot) => {
+            let frac_len = after_dot.bytes().take_while(|b| b.is_ascii_digit()).count();
+            after_dot.split_at(frac_len)
+        }
+        None => ("", rest),
+    };
+    if int_part.is_empty() && frac_part.is_empty() {
+        return word.to_string();
+    }
+    // E.g. `1.` or `.5.5`
+    if rest.starts_with('.') && frac_part.is_empty() || unit.starts_with('.') {
+        return word.to_string();
+    }
+
+    let int_part = int_part.trim_start_matches('0');
+    let frac_part = frac_part.trim_end_matches('0');
+    let is_zero = int_part.is_empty() && frac_part.is_empty();
+
+    // Inside e.g. `calc()`, a unitless zero is a number, not a length, so we keep the unit
+    if is_zero && (unit.is_empty() || (depth <= 0 && is_length_unit(unit))) {
+        return "0".to_string();
+    }
+
+    let mut result = String::with_capacity(word.len());
+    result.push_str(sign);
+    result.push_str(if int_part.is_empty() && !frac_part.is_empty() {
+        ""
+    } else if int_part.is_empty() {
+        "0"
+    } else {
+        int_part
+    });
+    if !frac_part.is_empty() {
+        result.push('.');
+        result.push_str(frac_part);
+    }
+    result.push_str(unit);
+    result
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '-' || c == '_' || c == '%'
+}
+
+fn is_length_unit(unit: &str) -> bool {
+    LENGTH_UNITS.iter().any(|u| u.eq_ignore_ascii_case(unit))
+}
+
+/// Main entrypoint. Minify the CSS by removing comments and insignificant whitespace.
+pub fn minify(css: &str) -> Result<String, CssError> {
+    let _span = tracing::debug_span!("minify_css", css_len = css.len()).entered();
+    let mut minifier = Minifier {
+        css,
+        out: String::with_capacity(css.len()),
+        pending_space: false,
+        last_strips_after: true,
+        depth: 0,
+        removed_comment: false,
+        after_custom_property: false,
+        in_custom_property: false,
+    };
+    minifier
+        .run()
+        .inspect_err(|e| tracing::debug!(error = %e, "Failed to minify CSS"))?;
+    tracing::debug!(output_len = minifier.out.len(), "Minified CSS");
+    Ok(minifier.out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_whitespace_and_comments() {
+        let input = r#"
+            /* Buttons */
+            .btn ,  .card > p {
+                color : red ;
+                margin: 0 auto;
+            }
+
+            .a .b:hover, .c ~ .d + .e { display: none !important; }
+        "#;
+        let expected = ".btn,.card>p{color:red;margin:0 auto}.a .b:hover,.c~.d+.e{display:none!important}";
+        assert_eq!(minify(input).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_preserves_significant_content() {
+        let test_cases = [
+            // Strings and URLs are kept as they are
+            (
+                ".a { content: \"a  ;  b\"; background: url( a b.png ); }",
+                ".a{content:\"a  ;  b\";background:url( a b.png )}",
+            ),
+            (
+                ".a { background: url(//cdn.example.com/a.png) }",
+                ".a{background:url(//cdn.example.com/a.png)}",
+            ),
+            // Spaces are required around `+` and `-` in calc()
+            (".a { width: calc(100% - 2px + 1em); }", ".a{width:calc(100% - 2px + 1em)}"),
+            // Descendant combinator before a pseudo-class
+            (".a :hover {}", ".a :hover{}"),
+            (".a :is(.b, .c) :hover { color : red }", ".a :is(.b,.c) :hover{color:red}"),
+            ("@media (min-width: 600px) { .a :hover { top : 0 } }", "@media (min-width:600px){.a :hover{top:0}}"),
+            // Important comments are kept
+            ("/*! License */ .a {}", "/*! License */.a{}"),
+            (
+                "@media screen and (min-width: 600px) { .a { color: red } }",
+                "@media screen and (min-width:600px){.a{color:red}}",
+            ),
+        ];
+
+        for (input, expected) in test_cases {
+            assert_eq!(minify(input).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn test_zero_values() {
+        let test_cases = [
+            (".a { margin: 0px 0.0em 0.50rem -0.5px; }", ".a{margin:0 0 .5rem -.5px}"),
+            (".a { opacity: 1.0; flex: 1 1 0%; }", ".a{opacity:1;flex:1 1 0%}"),
+            (".a { transition: opacity 0s; }", ".a{transition:opacity 0s}"),
+            (".a { width: calc(0px + 10%); }", ".a{width:calc(0px + 10%)}"),
+            ("@keyframes x { 0% { top: 0 } 100% { top: 10px } }", "@keyframes x{0%{top:0}100%{top:10px}}"),
+        ];
+
+        for (input, expected) in test_cases {
+            assert_eq!(minify(input).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn test_non_ascii() {
+        let test_cases = [
+            ("a { font-family: 日本語 }", "a{font-family:日本語}"),
+            (".ab日 {}", ".ab日{}"),
+            (".a✓b { background: URL(a.png) }", ".a✓b{background:URL(a.png)}"),
+        ];
+
+        for (input, expected) in test_cases {
+            assert_eq!(minify(input).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn test_removed_comments_separate_words() {
+        let test_cases = [
+            ("a{x:1px/*c*/2px}", "a{x:1px 2px}"),
+            (".a/*c*/.b{}", ".a.b{}"),
+            (".a/*c*/{x:1/**/;}", ".a{x:1}"),
+        ];
+
+        for (input, expected) in test_cases {
+            assert_eq!(minify(input).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn test_custom_properties() {
+        let test_cases = [
+            (
+                ".a { --x: 0px; width: calc(var(--x) + 0px); margin: 0px }",
+                ".a{--x:0px;width:calc(var(--x) + 0px);margin:0}",
+            ),
+            (".a { --y: 1.0 0.50em; top: 0.50em }", ".a{--y:1.0 0.50em;top:.5em}"),
+        ];
+
+        for (input, expected) in test_cases {
+            assert_eq!(minify(input).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn test_unicode_range() {
+        let test_cases = [
+            (
+                "@font-face { unicode-range: U+0025-00FF, u+0000; }",
+                "@font-face{unicode-range:U+0025-00FF,u+0000}",
+            ),
+            (".a { unicode-range: U+04?? }", ".a{unicode-range:U+04??}"),
+        ];
+
+        for (input, expected) in test_cases {
+            assert_eq!(minify(input).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn test_invalid_css() {
+        assert!(minify(".a { content: \"unclosed }").is_err());
+        assert!(minify(".a { } /* unclosed").is_err());
+    }
+}
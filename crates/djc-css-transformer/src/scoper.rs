@@ -0,0 +1,471 @@
+use std::fmt;
+
+// At-rules whose block contains regular style rules. Selectors inside these blocks are scoped too,
+// e.g. `@media (min-width: 600px) { .btn { ... } }`.
+const NESTED_RULE_AT_RULES: [&str; 6] = [
+    "media",
+    "supports",
+    "container",
+    "layer",
+    "document",
+    "scope",
+];
+
+/// Configuration for CSS scoping
+pub struct CssScopeConfig {
+    /// The attribute selector appended to selectors, e.g. `[data-djc-c1a2]`
+    attribute_selector: String,
+}
+
+impl CssScopeConfig {
+    pub fn new(scope_attribute: String) -> Self {
+        CssScopeConfig {
+            attribute_selector: format!("[{}]", scope_attribute),
+        }
+    }
+}
+
+/// Error raised when the CSS cannot be tokenized, e.g. because of an unclosed block or comment.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CssError {
+    pub message: String,
+    /// Byte offset into the input where the error was detected.
+    pub offset: usize,
+}
+
+impl fmt::Display for CssError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at position {}", self.message, self.offset)
+    }
+}
+
+impl std::error::Error for CssError {}
+
+/// Return the end of the comment starting at `start` (which points at `/*`)
+pub(crate) fn comment_end(css: &str, start: usize) -> Result<usize, CssError> {
+    match css[start + 2..].find("*/") {
+        Some(idx) => Ok(start + 2 + idx + 2),
+        None => Err(CssError {
+            message: "Unclosed comment".to_string(),
+            offset: start,
+        }),
+    }
+}
+
+/// Return the end of the string starting at `start` (which points at the opening quote)
+pub(crate) fn string_end(css: &str, start: usize) -> Result<usize, CssError> {
+    let bytes = css.as_bytes();
+    let quote = bytes[start];
+    let mut i = start + 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b if b == quote => return Ok(i + 1),
+            _ => i += 1,
+        }
+    }
+    Err(CssError {
+        message: "Unclosed string".to_string(),
+        offset: start,
+    })
+}
+
+/// Walks over the CSS source and copies it to the output, appending the scope attribute
+/// to each selector along the way. Everything else (whitespace, comments, declarations)
+/// is copied verbatim.
+struct Scoper<'a> {
+    config: &'a CssScopeConfig,
+    css: &'a str,
+    pos: usize,
+    out: String,
+}
+
+impl<'a> Scoper<'a> {
+    fn error(&self, message: &str, offset: usize) -> CssError {
+        CssError {
+            message: message.to_string(),
+            offset,
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.css.as_bytes().get(self.pos).copied()
+    }
+
+    fn starts_with(&self, pattern: &str) -> bool {
+        self.css[self.pos..].starts_with(pattern)
+    }
+
+    /// Copy input up to `end` to the output
+    fn copy_to(&mut self, end: usize) {
+        self.out.push_str(&self.css[self.pos..end]);
+        self.pos = end;
+    }
+
+    /// Scan forward from the current position and return the position of the first
+    /// byte from `stops` that is not inside a string, comment, or parentheses/brackets.
+    /// Returns the input length if none was found.
+    fn find_top_level(&self, stops: &[u8]) -> Result<usize, CssError> {
+        let bytes = self.css.as_bytes();
+        let mut depth: i32 = 0;
+        let mut i = self.pos;
+        while i < bytes.len() {
+            match bytes[i] {
+                b'\\' => i += 2,
+                b'"' | b'\'' => i = string_end(self.css, i)?,
+                b'/' if bytes.get(i + 1) == Some(&b'*') => i = comment_end(self.css, i)?,
+                b'(' | b'[' => {
+                    depth += 1;
+                    i += 1;
+                }
+                b')' | b']' => {
+                    depth -= 1;
+                    i += 1;
+                }
+                b if depth <= 0 && stops.contains(&b) => return Ok(i),
+                _ => i += 1,
+            }
+        }
+        Ok(bytes.len())
+    }
+
+    /// Copy a `{ ... }` block verbatim, including nested blocks. Expects the current
+    /// position to be at the opening brace.
+    fn copy_block(&mut self) -> Result<(), CssError> {
+        let open = self.pos;
+        let bytes = self.css.as_bytes();
+        let mut depth = 0;
+        let mut i = self.pos;
+        while i < bytes.len() {
+            match bytes[i] {
+                b'\\' => i += 2,
+                b'"' | b'\'' => i = string_end(self.css, i)?,
+                b'/' if bytes.get(i + 1) == Some(&b'*') => i = comment_end(self.css, i)?,
+                b'{' => {
+                    depth += 1;
+                    i += 1;
+                }
+                b'}' => {
+                    depth -= 1;
+                    i += 1;
+                    if depth == 0 {
+                        self.copy_to(i);
+                        return Ok(());
+                    }
+                }
+                _ => i += 1,
+            }
+        }
+        Err(self.error("Unclosed block", open))
+    }
+
+    /// Copy whitespace and comments. Returns once a significant character is reached.
+    fn copy_trivia(&mut self) -> Result<(), CssError> {
+        loop {
+            match self.peek() {
+                Some(b) if b.is_ascii_whitespace() => self.copy_to(self.pos + 1),
+                Some(b'/') if self.starts_with("/*") => {
+                    let end = comment_end(self.css, self.pos)?;
+                    self.copy_to(end);
+                }
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    /// Process a list of rules, either the whole stylesheet or the body of an at-rule
+    /// like `@media`. If `nested`, stops at (but does not consume) the closing brace.
+    fn process_rules(&mut self, nested: bool) -> Result<(), CssError> {
+        loop {
+            self.copy_trivia()?;
+            match self.peek() {
+                None if nested => return Err(self.error("Unclosed block", self.pos)),
+                None => return Ok(()),
+                Some(b'}') if nested => return Ok(()),
+                Some(b'}') => return Err(self.error("Unexpected '}'", self.pos)),
+                // Stray semicolons are allowed between rules
+                Some(b';') => self.copy_to(self.pos + 1),
+                Some(b'@') => self.process_at_rule()?,
+                Some(_) => self.process_style_rule()?,
+            }
+        }
+    }
+
+    /// Process e.g. `.btn, .card > p { color: red }`
+    fn process_style_rule(&mut self) -> Result<(), CssError> {
+        let start = self.pos;
+        let end = self.find_top_level(b"{};")?;
+        if self.css.as_bytes().get(end) != Some(&b'{') {
+            return Err(self.error("Expected '{' after selector", start));
+        }
+
+        let prelude = &self.css[start..end];
+        self.out.push_str(&scope_selector_list(prelude, &self.config.attribute_selector));
+        self.pos = end;
+        self.copy_block()
+    }
+
+    /// Process at-rules like `@media`, `@keyframes` or `@import`
+    fn process_at_rule(&mut self) -> Result<(), CssError> {
+        let start = self.pos;
+        let name_end = self.css[start + 1..]
+            .find(|c: char| !(c.is_alphanumeric() || c == '-' || c == '_'))
+            .map_or(self.css.len(), |idx| start + 1 + idx);
+        let name = self.css[start + 1..name_end].to_lowercase();
+
+        let end = self.find_top_level(b"{};")?;
+        match self.css.as_bytes().get(end) {
+            // Statement at-rules, e.g. `@import url("x.css");` or `@charset "utf-8";`
+            Some(b';') => self.copy_to(end + 1),
+            Some(b'{') if NESTED_RULE_AT_RULES.contains(&name.as_str()) => {
+                self.copy_to(end + 1);
+                self.process_rules(true)?;
+                self.copy_to(self.pos + 1);
+            }
+            // Everything else, e.g. `@keyframes`, `@font-face` or `@page`, contains
+            // no selectors that could be scoped, so we copy it as is.
+            Some(b'{') => {
+                self.copy_to(end);
+                self.copy_block()?;
+            }
+            // At-rule statement without the final semicolon at the end of input
+            None => self.copy_to(end),
+            Some(_) => return Err(self.error("Unexpected '}'", end)),
+        }
+        Ok(())
+    }
+}
+
+/// Add the attribute selector to each selector in a comma-separated selector list,
+/// e.g. `.a, .b > .c` -> `.a[data-x], .b > .c[data-x]`
+fn scope_selector_list(selector_list: &str, attribute_selector: &str) -> String {
+    let mut result = String::with_capacity(selector_list.len() + attribute_selector.len() * 2);
+    for (i, selector) in split_top_level(selector_list).into_iter().enumerate() {
+        if i > 0 {
+            result.push(',');
+        }
+        match scope_insert_position(selector) {
+            Some(pos) => {
+                result.push_str(&selector[..pos]);
+                result.push_str(attribute_selector);
+                result.push_str(&selector[pos..]);
+            }
+            None => result.push_str(selector),
+        }
+    }
+    result
+}
+
+/// Split the selector list on commas that are not inside `:is()`, `[...]`, strings, etc.
+fn split_top_level(selector_list: &str) -> Vec<&str> {
+    let bytes = selector_list.as_bytes();
+    let mut parts = Vec::new();
+    let mut depth: i32 = 0;
+    let mut quote: Option<u8> = None;
+    let mut part_start = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        let b = bytes[i];
+        if b == b'\\' {
+            i += 2;
+            continue;
+        }
+        match quote {
+            Some(q) if b == q => quote = None,
+            Some(_) => {}
+            None => match b {
+                b'"' | b'\'' => quote = Some(b),
+                b'(' | b'[' => depth += 1,
+                b')' | b']' => depth -= 1,
+                b',' if depth == 0 => {
+                    parts.push(&selector_list[part_start..i]);
+                    part_start = i + 1;
+                }
+                _ => {}
+            },
+        }
+        i += 1;
+    }
+    parts.push(&selector_list[part_start..]);
+    parts
+}
+
+/// Find where in a complex selector (e.g. `.card > p:hover::before`) the attribute selector
+/// should be inserted. That is at the end of the last compound selector, but before any
+/// trailing pseudo-classes or pseudo-elements (`.card > p[data-x]:hover::before`).
+///
+/// Returns `None` if the selector is empty.
+fn scope_insert_position(selector: &str) -> Option<usize> {
+    let bytes = selector.as_bytes();
+    let end = selector.trim_end().len();
+    if selector[..end].trim_start().is_empty() {
+        return None;
+    }
+
+    let mut depth: i32 = 0;
+    let mut quote: Option<u8> = None;
+    // First top-level `:` within the last compound selector
+    let mut first_colon: Option<usize> = None;
+    let mut i = 0;
+    while i < end {
+        let b = bytes[i];
+        if b == b'\\' {
+            i += 2;
+            continue;
+        }
+        match quote {
+            Some(q) if b == q => quote = None,
+            Some(_) => {}
+            None => match b {
+                b'"' | b'\'' => quote = Some(b),
+                b'(' | b'[' => depth += 1,
+                b')' | b']' => depth -= 1,
+                // Combinator, so a new compound selector starts
+                b' ' | b'\t' | b'\n' | b'\r' | b'\x0c' | b'>' | b'+' | b'~' if depth == 0 => {
+                    first_colon = None;
+                }
+                b':' if depth == 0 && first_colon.is_none() => {
+                    first_colon = Some(i);
+                }
+                _ => {}
+            },
+        }
+        i += 1;
+    }
+
+    Some(first_colon.unwrap_or(end))
+}
+
+/// Main entrypoint. Scope the CSS by appending the attribute selector to all selectors.
+pub fn scope(config: &CssScopeConfig, css: &str) -> Result<String, CssError> {
+    let _span = tracing::debug_span!("scope_css", css_len = css.len()).entered();
+    let mut scoper = Scoper {
+        config,
+        css,
+        pos: 0,
+        out: String::with_capacity(css.len() + css.len() / 4),
+    };
+    scoper
+        .process_rules(false)
+        .inspect_err(|e| tracing::debug!(error = %e, "Failed to scope CSS"))?;
+    tracing::debug!(output_len = scoper.out.len(), "Scoped CSS");
+    Ok(scoper.out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scope_with(css: &str) -> String {
+        let config = CssScopeConfig::new("data-djc-c1a2".to_string());
+        scope(&config, css).unwrap()
+    }
+
+    #[test]
+    fn test_simple_selectors() {
+        let test_cases = [
+            (".btn { color: red; }", ".btn[data-djc-c1a2] { color: red; }"),
+            ("div{color:red}", "div[data-djc-c1a2]{color:red}"),
+            ("*{margin:0}", "*[data-djc-c1a2]{margin:0}"),
+            ("#id.cls[type=\"a\"] {}", "#id.cls[type=\"a\"][data-djc-c1a2] {}"),
+            (
+                ".a, .b,\n.c {}",
+                ".a[data-djc-c1a2], .b[data-djc-c1a2],\n.c[data-djc-c1a2] {}",
+            ),
+        ];
+
+        for (input, expected) in test_cases {
+            assert_eq!(scope_with(input), expected);
+        }
+    }
+
+    #[test]
+    fn test_combinators() {
+        let test_cases = [
+            (".a .b {}", ".a .b[data-djc-c1a2] {}"),
+            (".a > .b {}", ".a > .b[data-djc-c1a2] {}"),
+            (".a>.b {}", ".a>.b[data-djc-c1a2] {}"),
+            (".a + .b ~ .c {}", ".a + .b ~ .c[data-djc-c1a2] {}"),
+        ];
+
+        for (input, expected) in test_cases {
+            assert_eq!(scope_with(input), expected);
+        }
+    }
+
+    #[test]
+    fn test_pseudo_classes_and_elements() {
+        let test_cases = [
+            (".a:hover {}", ".a[data-djc-c1a2]:hover {}"),
+            (".a::before {}", ".a[data-djc-c1a2]::before {}"),
+            (".a:hover::after {}", ".a[data-djc-c1a2]:hover::after {}"),
+            ("::selection {}", "[data-djc-c1a2]::selection {}"),
+            (".a:not(.b, .c) {}", ".a[data-djc-c1a2]:not(.b, .c) {}"),
+            (":is(.a, .b) .c {}", ":is(.a, .b) .c[data-djc-c1a2] {}"),
+            (".x :where(.a > .b) {}", ".x [data-djc-c1a2]:where(.a > .b) {}"),
+            // Escaped characters are part of the name, e.g. Tailwind classes
+            (".md\\:flex {}", ".md\\:flex[data-djc-c1a2] {}"),
+        ];
+
+        for (input, expected) in test_cases {
+            assert_eq!(scope_with(input), expected);
+        }
+    }
+
+    #[test]
+    fn test_at_rules() {
+        let input = r#"@charset "utf-8";
+@import url("base.css");
+@media (min-width: 600px) {
+    .a { color: red; }
+    @supports (display: grid) {
+        .b { display: grid; }
+    }
+}
+@keyframes spin {
+    from { transform: rotate(0deg); }
+    50% { transform: rotate(180deg); }
+}
+@font-face { font-family: "X"; src: url(x.woff); }"#;
+
+        let expected = r#"@charset "utf-8";
+@import url("base.css");
+@media (min-width: 600px) {
+    .a[data-djc-c1a2] { color: red; }
+    @supports (display: grid) {
+        .b[data-djc-c1a2] { display: grid; }
+    }
+}
+@keyframes spin {
+    from { transform: rotate(0deg); }
+    50% { transform: rotate(180deg); }
+}
+@font-face { font-family: "X"; src: url(x.woff); }"#;
+
+        assert_eq!(scope_with(input), expected);
+    }
+
+    #[test]
+    fn test_declarations_and_comments_are_preserved() {
+        let input = "/* .x {} */\n.a { content: \"}\"; background: url(a.png) /* } */; }";
+        let expected =
+            "/* .x {} */\n.a[data-djc-c1a2] { content: \"}\"; background: url(a.png) /* } */; }";
+        assert_eq!(scope_with(input), expected);
+    }
+
+    #[test]
+    fn test_invalid_css() {
+        let config = CssScopeConfig::new("data-djc-c1a2".to_string());
+        let invalid_cases = [
+            ".a { color: red;",
+            ".a { color: red; }}",
+            "/* unclosed",
+            ".a",
+            "@media print { .a {}",
+        ];
+
+        for input in invalid_cases {
+            assert!(scope(&config, input).is_err(), "{}", input);
+        }
+    }
+}
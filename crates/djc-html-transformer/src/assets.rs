@@ -0,0 +1,264 @@
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::reader::Reader;
+use xxhash_rust::xxh3::xxh3_128;
+
+use crate::transformer::TransformError;
+
+/// Kind of the asset that the HTML references or contains
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AssetKind {
+    /// `<script src="...">`
+    Script,
+    /// `<link href="...">`, e.g. a stylesheet, icon or preload
+    Link,
+    /// `<img src="...">`
+    Image,
+    /// `<script>` with the code inside
+    InlineScript,
+    /// `<style>` with the CSS inside
+    InlineStyle,
+}
+
+impl AssetKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AssetKind::Script => "script",
+            AssetKind::Link => "link",
+            AssetKind::Image => "image",
+            AssetKind::InlineScript => "inline_script",
+            AssetKind::InlineStyle => "inline_style",
+        }
+    }
+}
+
+/// Asset found in the HTML
+#[derive(Debug, Clone, PartialEq)]
+pub struct Asset {
+    pub kind: AssetKind,
+    /// Value of the `src` or `href` attribute. `None` for inline assets.
+    pub url: Option<String>,
+    /// Value of the `rel` attribute of `<link>`, e.g. `stylesheet`
+    pub rel: Option<String>,
+    /// Content of inline assets
+    pub content: Option<String>,
+    /// 128-bit XXH3 hash of the content of inline assets
+    pub hash: Option<u128>,
+    /// Byte offsets of the start tag in the HTML
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Find the assets that the HTML references (`<script src>`, `<link href>`, `<img src>`)
+/// or contains (`<script>` and `<style>` blocks), in the order of appearance.
+///
+/// Content of `<script>` and `<style>` is not parsed as HTML, so it can contain e.g. `a < b`.
+pub fn extract_html_assets(
+    html: &str,
+) -> Result<Vec<Asset>, Box<dyn std::error::Error + Send + Sync>> {
+    let _span = tracing::debug_span!("extract_html_assets", html_len = html.len()).entered();
+
+    let mut assets = Vec::new();
+    // Offset of the part of the HTML that the reader reads. The reader is recreated
+    // after each `<script>` and `<style>` element, because their content is skipped outside of it.
+    let mut base = 0;
+    let mut reader = new_reader(html);
+
+    loop {
+        let start = base + reader.buffer_position() as usize;
+        let event = reader.read_event().map_err(|e| {
+            let offset = base + reader.error_position() as usize;
+            tracing::debug!(position = offset, error = %e, "Failed to parse HTML");
+            TransformError::new(html, offset, e.to_string())
+        })?;
+        let end = base + reader.buffer_position() as usize;
+
+        let (element, has_content) = match event {
+            Event::Start(e) => (e, true),
+            Event::Empty(e) => (e, false),
+            Event::Eof => break,
+            _ => continue,
+        };
+        let name = element.name().as_ref().to_ascii_lowercase();
+        let asset = |kind, url| Asset {
+            kind,
+            url,
+            rel: None,
+            content: None,
+            hash: None,
+            start,
+            end,
+        };
+
+        match name.as_slice() {
+            b"script" | b"style" => {
+                let content = if has_content {
+                    // Skip the content, and continue reading from its end tag
+                    let content_end = find_end_tag(html, end, &name);
+                    base = content_end;
+                    reader = new_reader(&html[base..]);
+                    &html[end..content_end]
+                } else {
+                    ""
+                };
+
+                if name == b"style" {
+                    assets.push(Asset {
+                        content: Some(content.to_string()),
+                        hash: Some(xxh3_128(content.as_bytes())),
+                        ..asset(AssetKind::InlineStyle, None)
+                    });
+                } else if let Some(src) = attribute(&element, b"src") {
+                    assets.push(asset(AssetKind::Script, Some(src)));
+                } else {
+                    assets.push(Asset {
+                        content: Some(content.to_string()),
+                        hash: Some(xxh3_128(content.as_bytes())),
+                        ..asset(AssetKind::InlineScript, None)
+                    });
+                }
+            }
+            b"link" => {
+                if let Some(href) = attribute(&element, b"href") {
+                    assets.push(Asset {
+                        rel: attribute(&element, b"rel"),
+                        ..asset(AssetKind::Link, Some(href))
+                    });
+                }
+            }
+            b"img" => {
+                if let Some(src) = attribute(&element, b"src") {
+                    assets.push(asset(AssetKind::Image, Some(src)));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    tracing::debug!(assets = assets.len(), "Extracted HTML assets");
+    Ok(assets)
+}
+
+fn new_reader(html: &str) -> Reader<&[u8]> {
+    let mut reader = Reader::from_str(html);
+    let reader_config = reader.config_mut();
+    // Only the tags are of interest, so don't fail on misnested end tags. The reader
+    // doesn't know about the elements opened before it was recreated, so allow those too.
+    reader_config.check_end_names = false;
+    reader_config.allow_unmatched_ends = true;
+    reader_config.allow_dangling_amp = true;
+    reader
+}
+
+/// Find where the content of e.g. `<script>` that starts at `from` ends, i.e. the start
+/// of its end tag. Returns the end of the HTML if the tag isn't closed.
+fn find_end_tag(html: &str, from: usize, name: &[u8]) -> usize {
+    let bytes = html.as_bytes();
+    let mut pos = from;
+    while let Some(idx) = html[pos..].find("</") {
+        let tag_start = pos + idx;
+        let name_end = tag_start + 2 + name.len();
+        if bytes.len() >= name_end
+            && bytes[tag_start + 2..name_end].eq_ignore_ascii_case(name)
+            && bytes
+                .get(name_end)
+                .is_none_or(|b| b.is_ascii_whitespace() || *b == b'>')
+        {
+            return tag_start;
+        }
+        pos = tag_start + 2;
+    }
+    html.len()
+}
+
+/// Value of the attribute with the given name, with the entities unescaped
+fn attribute(element: &BytesStart, name: &[u8]) -> Option<String> {
+    let attr = element
+        .html_attributes()
+        .flatten()
+        .find(|attr| attr.key.as_ref().eq_ignore_ascii_case(name))?;
+    let value = match attr.unescape_value() {
+        Ok(value) => value.into_owned(),
+        // E.g. `&` that's not part of an entity
+        Err(_) => String::from_utf8_lossy(&attr.value).into_owned(),
+    };
+    Some(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_html_assets() {
+        let html = concat!(
+            "<head>\n",
+            "  <link rel=\"stylesheet\" href=\"/static/app.css?v=1&amp;x=2\">\n",
+            "  <script type=\"module\" src=\"/static/app.js\"></script>\n",
+            "  <style>p { color: red }</style>\n",
+            "</head>\n",
+            "<body><img src=\"logo.png\" /><img alt=\"no src\">",
+            "<script>if (a < b) { document.write('</p>') }</script></body>",
+        );
+        let assets = extract_html_assets(html).unwrap();
+
+        let summary: Vec<_> = assets
+            .iter()
+            .map(|a| (a.kind, a.url.as_deref(), a.rel.as_deref()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (
+                    AssetKind::Link,
+                    Some("/static/app.css?v=1&x=2"),
+                    Some("stylesheet")
+                ),
+                (AssetKind::Script, Some("/static/app.js"), None),
+                (AssetKind::InlineStyle, None, None),
+                (AssetKind::Image, Some("logo.png"), None),
+                (AssetKind::InlineScript, None, None),
+            ]
+        );
+
+        let style = &assets[2];
+        assert_eq!(style.content.as_deref(), Some("p { color: red }"));
+        assert_eq!(style.hash, Some(xxh3_128(b"p { color: red }")));
+        assert_eq!(&html[style.start..style.end], "<style>");
+
+        // Offsets are still correct after the content of `<script>` was skipped
+        let script = &assets[4];
+        assert_eq!(
+            script.content.as_deref(),
+            Some("if (a < b) { document.write('</p>') }")
+        );
+        assert_eq!(&html[script.start..script.end], "<script>");
+        let image = &assets[3];
+        assert_eq!(&html[image.start..image.end], "<img src=\"logo.png\" />");
+    }
+
+    #[test]
+    fn test_extract_html_assets_unquoted_attributes() {
+        let html = "<link rel=stylesheet href=s.css><script src=a.js></script><img src=x.png alt=logo>";
+        let assets = extract_html_assets(html).unwrap();
+
+        let summary: Vec<_> = assets
+            .iter()
+            .map(|a| (a.kind, a.url.as_deref(), a.rel.as_deref()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (AssetKind::Link, Some("s.css"), Some("stylesheet")),
+                (AssetKind::Script, Some("a.js"), None),
+                (AssetKind::Image, Some("x.png"), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_extract_html_assets_unclosed_script() {
+        let assets = extract_html_assets("<script>let a = 1;").unwrap();
+        assert_eq!(assets.len(), 1);
+        assert_eq!(assets[0].content.as_deref(), Some("let a = 1;"));
+    }
+}
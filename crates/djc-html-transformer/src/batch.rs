@@ -0,0 +1,121 @@
+use crate::transformer::{transform, HtmlTransformerConfig, TransformResult};
+
+/// Result of transforming one of the HTML strings of a batch
+pub type BatchResult = Result<TransformResult, Box<dyn std::error::Error + Send + Sync>>;
+
+/// Transform each of the HTML strings with the same configuration, one after another.
+///
+/// Results are in the same order as the inputs. A malformed HTML string doesn't stop
+/// the processing of the rest of the batch.
+pub fn transform_many<S: AsRef<str>>(
+    config: &HtmlTransformerConfig,
+    htmls: &[S],
+) -> Vec<BatchResult> {
+    htmls
+        .iter()
+        .map(|html| transform(config, html.as_ref()))
+        .collect()
+}
+
+/// Same as [`transform_many`], but the HTML strings are transformed in parallel
+/// on a rayon thread pool.
+///
+/// If `num_threads` is set, a dedicated thread pool with that many threads is used.
+/// Otherwise the HTML is transformed on rayon's global thread pool, which by default
+/// has as many threads as there are CPUs.
+#[cfg(feature = "parallel")]
+pub fn transform_many_parallel<S: AsRef<str> + Sync>(
+    config: &HtmlTransformerConfig,
+    htmls: &[S],
+    num_threads: Option<usize>,
+) -> Result<Vec<BatchResult>, rayon::ThreadPoolBuildError> {
+    use rayon::prelude::*;
+
+    let run = || {
+        htmls
+            .par_iter()
+            .map(|html| transform(config, html.as_ref()))
+            .collect()
+    };
+
+    match num_threads {
+        Some(num_threads) => {
+            let pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(num_threads)
+                .build()?;
+            Ok(pool.install(run))
+        }
+        None => Ok(run()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> HtmlTransformerConfig {
+        HtmlTransformerConfig::new(
+            vec!["data-root".to_string()],
+            vec!["data-v-123".to_string()],
+            true,
+            Some("data-id".to_string()),
+        )
+    }
+
+    fn outputs(results: Vec<BatchResult>) -> Vec<Result<String, String>> {
+        results
+            .into_iter()
+            .map(|r| r.map(|r| r.html).map_err(|e| e.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_transform_many() {
+        let htmls = ["<div data-id=\"1\">A</div>", "<p>B</p><span>C</span>"];
+        let results = transform_many(&config(), &htmls);
+
+        assert_eq!(results.len(), 2);
+        let first = results[0].as_ref().unwrap();
+        assert_eq!(
+            first.html,
+            r#"<div data-id="1" data-root="" data-v-123="">A</div>"#
+        );
+        assert_eq!(
+            first.captured,
+            vec![(
+                "1".to_string(),
+                vec!["data-root".to_string(), "data-v-123".to_string()]
+            )]
+        );
+        assert_eq!(
+            results[1].as_ref().unwrap().html,
+            r#"<p data-root="" data-v-123="">B</p><span data-root="" data-v-123="">C</span>"#
+        );
+    }
+
+    #[test]
+    fn test_transform_many_keeps_going_after_error() {
+        let htmls = ["<div>A</div>", "<div>B</span>", "<p>C</p>"];
+        let results = outputs(transform_many(&config(), &htmls));
+
+        assert!(results[0].is_ok());
+        assert!(results[1].is_err());
+        assert!(results[2].is_ok());
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_transform_many_parallel() {
+        let htmls: Vec<String> = (0..200)
+            .map(|i| format!("<div data-id=\"{i}\"><p>{i}</p></div>"))
+            .chain(std::iter::once("<div>B</span>".to_string()))
+            .collect();
+        let config = config();
+        let expected = outputs(transform_many(&config, &htmls));
+
+        for num_threads in [None, Some(1), Some(4)] {
+            let results = transform_many_parallel(&config, &htmls, num_threads).unwrap();
+            assert_eq!(outputs(results), expected);
+        }
+    }
+}
@@ -0,0 +1,339 @@
+use lru::LruCache;
+use std::hash::Hash;
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, MutexGuard};
+use xxhash_rust::xxh3::Xxh3;
+
+use crate::transformer::{transform, HtmlTransformerConfig, TransformResult};
+
+/// Snapshot of the cache statistics, similar to Python's `functools.lru_cache().cache_info()`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CacheInfo {
+    pub hits: u64,
+    pub misses: u64,
+    /// Maximum number of entries. 0 if the cache is disabled.
+    pub maxsize: usize,
+    pub currsize: usize,
+    /// Approximate number of bytes taken by the cached results
+    pub heap_size: usize,
+}
+
+/// Cached result, with the input it was transformed from
+struct CacheEntry {
+    // Compared on lookup, as different inputs can have the same hash
+    config: HtmlTransformerConfig,
+    html: Box<str>,
+    result: TransformResult,
+}
+
+struct CacheState {
+    // `None` if the cache is disabled
+    entries: Option<LruCache<u128, CacheEntry>>,
+    hits: u64,
+    misses: u64,
+    // Sum of `entry_size()` of the entries, kept up to date as entries are added and evicted
+    heap_size: usize,
+}
+
+/// Thread-safe LRU cache of transformation results.
+///
+/// Entries are looked up by a 128-bit hash of the input HTML and the config. The hash is
+/// not cryptographic, and the HTML can contain user input, so the entries also keep the input,
+/// and a result is returned only if its input is equal. Errors are not cached.
+pub struct TransformCache {
+    state: Mutex<CacheState>,
+    // Whether `state.entries` is set. Checked before taking the lock, so that threads
+    // don't wait for each other when the cache is disabled.
+    enabled: AtomicBool,
+}
+
+impl TransformCache {
+    /// Create a cache that holds up to `maxsize` results. With `maxsize` of 0 the cache is disabled.
+    pub fn new(maxsize: usize) -> Self {
+        let cache = Self::disabled();
+        cache.resize(maxsize);
+        cache
+    }
+
+    /// Create a disabled cache. Can be used to initialize a `static`, and enabled later with `resize()`.
+    pub const fn disabled() -> Self {
+        TransformCache {
+            state: Mutex::new(CacheState {
+                entries: None,
+                hits: 0,
+                misses: 0,
+                heap_size: 0,
+            }),
+            enabled: AtomicBool::new(false),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, CacheState> {
+        // The state is always consistent, so it's safe to use even if another thread panicked
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Change the maximum number of entries, evicting the least recently used ones if needed.
+    /// Setting `maxsize` to 0 disables the cache and drops all entries.
+    pub fn resize(&self, maxsize: usize) {
+        let mut state = self.lock();
+        let state = &mut *state;
+        match (NonZeroUsize::new(maxsize), state.entries.as_mut()) {
+            (None, _) => {
+                state.entries = None;
+                state.heap_size = 0;
+            }
+            (Some(maxsize), Some(entries)) => {
+                // Evict here, as `resize()` drops the evicted entries without returning them
+                while entries.len() > maxsize.get() {
+                    if let Some((_, evicted)) = entries.pop_lru() {
+                        state.heap_size -= entry_size(&evicted);
+                    }
+                }
+                entries.resize(maxsize);
+            }
+            (Some(maxsize), None) => state.entries = Some(LruCache::new(maxsize)),
+        }
+        self.enabled
+            .store(state.entries.is_some(), Ordering::Relaxed);
+    }
+
+    /// Remove all entries and reset the statistics
+    pub fn clear(&self) {
+        let mut state = self.lock();
+        if let Some(entries) = state.entries.as_mut() {
+            entries.clear();
+        }
+        state.hits = 0;
+        state.misses = 0;
+        state.heap_size = 0;
+    }
+
+    pub fn info(&self) -> CacheInfo {
+        let state = self.lock();
+        CacheInfo {
+            hits: state.hits,
+            misses: state.misses,
+            maxsize: state.entries.as_ref().map_or(0, |e| e.cap().get()),
+            currsize: state.entries.as_ref().map_or(0, |e| e.len()),
+            heap_size: state.heap_size,
+        }
+    }
+
+    /// Return the cached result for the HTML and config, or transform the HTML and cache the result.
+    ///
+    /// If the cache is disabled, the HTML is always transformed.
+    ///
+    /// The warnings are returned only by the call that transformed the HTML, so that each
+    /// problem is reported once. Cached results are returned without warnings.
+    pub fn get_or_transform(
+        &self,
+        config: &HtmlTransformerConfig,
+        html: &str,
+    ) -> Result<TransformResult, Box<dyn std::error::Error + Send + Sync>> {
+        if !self.enabled.load(Ordering::Relaxed) {
+            return transform(config, html);
+        }
+        let key = cache_key(config, html);
+        // Events are emitted only after the lock is released, as they can call into
+        // Python logging handlers, which can e.g. wait for another thread
+        {
+            let mut state = self.lock();
+            let Some(entries) = state.entries.as_mut() else {
+                drop(state);
+                return transform(config, html);
+            };
+            let entry = entries
+                .get(&key)
+                .filter(|entry| entry.config == *config && *entry.html == *html);
+            if let Some(entry) = entry {
+                let result = TransformResult {
+                    warnings: Vec::new(),
+                    ..entry.result.clone()
+                };
+                state.hits += 1;
+                drop(state);
+                tracing::trace!("Cache hit");
+                return Ok(result);
+            }
+            state.misses += 1;
+        }
+        tracing::trace!("Cache miss");
+
+        // Transform without holding the lock, so other threads are not blocked meanwhile
+        let result = transform(config, html)?;
+        let mut state = self.lock();
+        let state = &mut *state;
+        if let Some(entries) = state.entries.as_mut() {
+            // Clones don't keep the spare capacity, so measure the clone that is cached
+            let cached = CacheEntry {
+                config: config.clone(),
+                html: html.into(),
+                result: result.clone(),
+            };
+            state.heap_size += entry_size(&cached);
+            // Either the least recently used entry is evicted, or another thread has cached
+            // the same result meanwhile, or the entry of a different input with the same hash
+            if let Some((_, removed)) = entries.push(key, cached) {
+                state.heap_size -= entry_size(&removed);
+            }
+        }
+        Ok(result)
+    }
+}
+
+/// Approximate number of bytes taken by a cache entry, including the key. The config
+/// is usually much smaller than the HTML, so it's not counted.
+fn entry_size(entry: &CacheEntry) -> usize {
+    std::mem::size_of::<(u128, CacheEntry)>() + entry.html.len() + entry.result.heap_size()
+}
+
+fn cache_key(config: &HtmlTransformerConfig, html: &str) -> u128 {
+    let mut hasher = Xxh3::new();
+    config.hash(&mut hasher);
+    html.hash(&mut hasher);
+    hasher.digest128()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(all_attributes: &[&str]) -> HtmlTransformerConfig {
+        let all_attributes = all_attributes.iter().map(|s| s.to_string()).collect();
+        HtmlTransformerConfig::new(vec![], all_attributes, true, None)
+    }
+
+    fn entry(config: &HtmlTransformerConfig, html: &str, result: &TransformResult) -> CacheEntry {
+        CacheEntry {
+            config: config.clone(),
+            html: html.into(),
+            result: result.clone(),
+        }
+    }
+
+    #[test]
+    fn test_cache_hits_and_misses() {
+        let cache = TransformCache::new(10);
+        let config_a = config(&["data-a"]);
+        let config_b = config(&["data-b"]);
+
+        let first = cache.get_or_transform(&config_a, "<div></div>").unwrap();
+        let second = cache.get_or_transform(&config_a, "<div></div>").unwrap();
+        assert_eq!(first.html, r#"<div data-a=""></div>"#);
+        assert_eq!(second.html, first.html);
+
+        // Same HTML with a different config is a different entry
+        let other = cache.get_or_transform(&config_b, "<div></div>").unwrap();
+        assert_eq!(other.html, r#"<div data-b=""></div>"#);
+
+        // Errors are not cached
+        assert!(cache.get_or_transform(&config_a, "<div></span>").is_err());
+
+        // The cache holds clones of the results
+        let heap_size = entry_size(&entry(&config_a, "<div></div>", &first))
+            + entry_size(&entry(&config_b, "<div></div>", &other));
+        assert_eq!(
+            cache.info(),
+            CacheInfo {
+                hits: 1,
+                misses: 3,
+                maxsize: 10,
+                currsize: 2,
+                heap_size,
+            }
+        );
+
+        cache.clear();
+        assert_eq!(
+            cache.info(),
+            CacheInfo {
+                hits: 0,
+                misses: 0,
+                maxsize: 10,
+                currsize: 0,
+                heap_size: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn test_cache_hits_have_no_warnings() {
+        let cache = TransformCache::new(10);
+        let config = config(&["data-v"]);
+
+        let first = cache.get_or_transform(&config, "<div><span>").unwrap();
+        assert_eq!(first.warnings.len(), 2);
+        let second = cache.get_or_transform(&config, "<div><span>").unwrap();
+        assert_eq!(second.html, first.html);
+        assert!(second.warnings.is_empty());
+
+        // Without the cache, the warnings are returned every time
+        cache.resize(0);
+        let third = cache.get_or_transform(&config, "<div><span>").unwrap();
+        assert_eq!(third.warnings, first.warnings);
+    }
+
+    #[test]
+    fn test_cache_hash_collision() {
+        let cache = TransformCache::new(10);
+        let config = config(&["data-v"]);
+        let other = transform(&config, "<b></b>").unwrap();
+
+        // Pretend that `<b></b>` has the same hash as `<a></a>`
+        let key = cache_key(&config, "<a></a>");
+        let cached = entry(&config, "<b></b>", &other);
+        cache.lock().entries.as_mut().unwrap().push(key, cached);
+
+        let result = cache.get_or_transform(&config, "<a></a>").unwrap();
+        assert_eq!(result.html, r#"<a data-v=""></a>"#);
+        assert_eq!(cache.info().hits, 0);
+
+        // The entry was replaced by the one for `<a></a>`
+        let result = cache.get_or_transform(&config, "<a></a>").unwrap();
+        assert_eq!(result.html, r#"<a data-v=""></a>"#);
+        assert_eq!(cache.info().hits, 1);
+    }
+
+    #[test]
+    fn test_cache_eviction_and_resize() {
+        let cache = TransformCache::new(2);
+        let config = config(&["data-v"]);
+
+        for html in ["<a></a>", "<b></b>", "<i></i>"] {
+            cache.get_or_transform(&config, html).unwrap();
+        }
+        assert_eq!(cache.info().currsize, 2);
+
+        // `<a>` was evicted as the least recently used entry
+        cache.get_or_transform(&config, "<a></a>").unwrap();
+        assert_eq!(cache.info().hits, 0);
+        cache.get_or_transform(&config, "<i></i>").unwrap();
+        assert_eq!(cache.info().hits, 1);
+
+        let i = cache.get_or_transform(&config, "<i></i>").unwrap();
+        cache.resize(1);
+        assert_eq!(cache.info().currsize, 1);
+        assert_eq!(
+            cache.info().heap_size,
+            entry_size(&entry(&config, "<i></i>", &i))
+        );
+
+        // Disabled cache stores nothing and doesn't count the calls
+        let misses = cache.info().misses;
+        cache.resize(0);
+        cache.get_or_transform(&config, "<a></a>").unwrap();
+        assert_eq!(cache.info().maxsize, 0);
+        assert_eq!(cache.info().currsize, 0);
+        assert_eq!(cache.info().heap_size, 0);
+        assert_eq!(cache.info().misses, misses);
+
+        // Enabled again
+        cache.resize(1);
+        cache.get_or_transform(&config, "<a></a>").unwrap();
+        cache.get_or_transform(&config, "<a></a>").unwrap();
+        assert_eq!(cache.info().currsize, 1);
+        assert_eq!(cache.info().misses, misses + 1);
+    }
+}
@@ -0,0 +1,147 @@
+use encoding_rs::{Encoding, UTF_8};
+use std::borrow::Cow;
+
+// Same as browsers, only the start of the document is searched for the encoding declaration
+const PRESCAN_LENGTH: usize = 1024;
+
+/// Decode the raw bytes of a HTML file to a string.
+///
+/// The encoding is determined the same way as browsers do, in order of priority:
+/// 1. Byte order mark (BOM), e.g. of UTF-8 or UTF-16
+/// 2. Encoding declaration in the first 1024 bytes, i.e. `<meta charset="...">`,
+///    `<meta http-equiv="Content-Type" content="text/html; charset=...">`
+///    or `<?xml version="1.0" encoding="..."?>`
+/// 3. UTF-8 otherwise
+///
+/// The BOM is removed, and malformed byte sequences are replaced with U+FFFD.
+///
+/// Returns the decoded string and the encoding that was used.
+pub fn decode_html(bytes: &[u8]) -> (Cow<'_, str>, &'static Encoding) {
+    let declared = declared_encoding(bytes).unwrap_or(UTF_8);
+    // `decode()` sniffs the BOM, which takes precedence over the declared encoding
+    let (text, encoding, _had_errors) = declared.decode(bytes);
+    (text, encoding)
+}
+
+/// Find the encoding declared in the HTML, if any
+fn declared_encoding(bytes: &[u8]) -> Option<&'static Encoding> {
+    let head = &bytes[..bytes.len().min(PRESCAN_LENGTH)];
+    let head = head.to_ascii_lowercase();
+
+    let label = if head.starts_with(b"<?xml") {
+        let decl_end = find(&head, b"?>", 0)?;
+        attribute_value(&head[..decl_end], b"encoding=")
+    } else {
+        let mut label = None;
+        let mut pos = 0;
+        while let Some(meta_start) = find(&head, b"<meta", pos) {
+            let meta_end = find(&head, b">", meta_start).unwrap_or(head.len());
+            // Covers both `charset="utf-8"` and `content="text/html; charset=utf-8"`
+            label = attribute_value(&head[meta_start..meta_end], b"charset=");
+            if label.is_some() {
+                break;
+            }
+            pos = meta_end;
+        }
+        label
+    }?;
+
+    let encoding = Encoding::for_label(label)?;
+    // Documents cannot declare themselves as UTF-16 in ASCII-compatible bytes, see
+    // https://html.spec.whatwg.org/multipage/parsing.html#prescan-a-byte-stream-to-determine-its-encoding
+    if encoding == encoding_rs::UTF_16LE || encoding == encoding_rs::UTF_16BE {
+        return Some(UTF_8);
+    }
+    Some(encoding)
+}
+
+fn find(haystack: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    haystack
+        .get(from..)?
+        .windows(needle.len())
+        .position(|window| window == needle)
+        .map(|idx| from + idx)
+}
+
+/// Get the value after `key`, e.g. `utf-8` from `charset="utf-8"`, with or without quotes
+fn attribute_value<'a>(tag: &'a [u8], key: &[u8]) -> Option<&'a [u8]> {
+    let start = find(tag, key, 0)? + key.len();
+    let value = &tag[start..];
+    let value = match value.first() {
+        Some(&quote @ (b'"' | b'\'')) => {
+            let value = &value[1..];
+            let end = value
+                .iter()
+                .position(|&b| b == quote)
+                .unwrap_or(value.len());
+            &value[..end]
+        }
+        _ => {
+            let end = value
+                .iter()
+                .position(|&b| b.is_ascii_whitespace() || matches!(b, b';' | b'/' | b'"' | b'\''))
+                .unwrap_or(value.len());
+            &value[..end]
+        }
+    };
+    (!value.is_empty()).then_some(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bom() {
+        let (text, encoding) = decode_html(b"\xEF\xBB\xBF<p>Hi</p>");
+        assert_eq!(text, "<p>Hi</p>");
+        assert_eq!(encoding, UTF_8);
+
+        let (text, encoding) = decode_html(b"\xFF\xFE<\x00p\x00>\x00");
+        assert_eq!(text, "<p>");
+        assert_eq!(encoding, encoding_rs::UTF_16LE);
+
+        // BOM takes precedence over the declaration
+        let (text, encoding) = decode_html(b"\xEF\xBB\xBF<meta charset=\"latin1\">\xC3\xA9");
+        assert_eq!(text, "<meta charset=\"latin1\">\u{e9}");
+        assert_eq!(encoding, UTF_8);
+    }
+
+    #[test]
+    fn test_declared_encoding() {
+        let cases: [(&[u8], &str); 6] = [
+            (b"<meta charset=\"ISO-8859-2\"><p>\xB1</p>", "<meta charset=\"ISO-8859-2\"><p>\u{105}</p>"),
+            (b"<META CHARSET=windows-1252>\x80", "<META CHARSET=windows-1252>\u{20ac}"),
+            (
+                b"<meta name=\"x\"><meta http-equiv=\"Content-Type\" content=\"text/html; charset=latin1\">\xE9",
+                "<meta name=\"x\"><meta http-equiv=\"Content-Type\" content=\"text/html; charset=latin1\">\u{e9}",
+            ),
+            (
+                b"<?xml version=\"1.0\" encoding='iso-8859-1'?><p>\xE9</p>",
+                "<?xml version=\"1.0\" encoding='iso-8859-1'?><p>\u{e9}</p>",
+            ),
+            // UTF-16 declaration in ASCII bytes is treated as UTF-8
+            (b"<meta charset=\"utf-16\">\xC3\xA9", "<meta charset=\"utf-16\">\u{e9}"),
+            // Unknown encoding falls back to UTF-8
+            (b"<meta charset=\"foo\">\xC3\xA9", "<meta charset=\"foo\">\u{e9}"),
+        ];
+        for (bytes, expected) in cases {
+            assert_eq!(decode_html(bytes).0, expected);
+        }
+    }
+
+    #[test]
+    fn test_default_utf8() {
+        let (text, encoding) = decode_html("<p>Ahoj světe</p>".as_bytes());
+        assert_eq!(text, "<p>Ahoj světe</p>");
+        assert_eq!(encoding, UTF_8);
+
+        // Declaration after the first 1024 bytes is ignored
+        let mut bytes = vec![b' '; PRESCAN_LENGTH];
+        bytes.extend_from_slice(b"<meta charset=\"latin1\">\xC3\xA9");
+        assert!(decode_html(&bytes).0.ends_with('\u{e9}'));
+
+        // Malformed bytes are replaced
+        assert_eq!(decode_html(b"<p>\xFF</p>").0, "<p>\u{fffd}</p>");
+    }
+}
@@ -0,0 +1,362 @@
+use xxhash_rust::xxh3::xxh3_128;
+
+// Elements whose content is hashed as is, because whitespace in it matters
+const RAW_TEXT_ELEMENTS: [&str; 4] = ["pre", "script", "style", "textarea"];
+
+/// Fingerprint HTML, e.g. to use as a cache key for transformed fragments.
+///
+/// Insignificant whitespace is ignored, so HTML that differs only in indentation
+/// or line breaks has the same hash:
+/// - Runs of whitespace in text are collapsed into a single space, and trimmed at the start and end.
+/// - Whitespace between attributes is collapsed, and removed around `=` and before `>` and `/>`.
+///
+/// Attribute values, comments, and content of `<pre>`, `<script>`, `<style>` and `<textarea>`
+/// are hashed as is.
+pub fn hash_html(html: &str) -> u128 {
+    xxh3_128(&Normalizer::new(html, false).run())
+}
+
+/// Fingerprint Django template source, e.g. to use as a cache key for compiled templates.
+///
+/// Same as `hash_html()`, but also ignores whitespace inside `{{ }}` and `{% %}` tags,
+/// so e.g. `{{ name }}` and `{{name}}` have the same hash. String literals inside tags,
+/// `{# #}` comments and the content of `{% verbatim %}` blocks are hashed as is.
+pub fn hash_template(source: &str) -> u128 {
+    xxh3_128(&Normalizer::new(source, true).run())
+}
+
+/// Single pass over the input that copies it with the insignificant whitespace removed.
+///
+/// Works on bytes, because all the syntax is ASCII. Bytes of multi-byte characters
+/// are always copied together, so the output is still valid UTF-8.
+struct Normalizer<'a> {
+    src: &'a [u8],
+    pos: usize,
+    out: Vec<u8>,
+    // Whether to also normalize Django template tags
+    template: bool,
+    // Whether whitespace was skipped, and a single space should be written before the next content
+    pending_space: bool,
+}
+
+impl<'a> Normalizer<'a> {
+    fn new(src: &'a str, template: bool) -> Self {
+        Self {
+            src: src.as_bytes(),
+            pos: 0,
+            out: Vec::with_capacity(src.len()),
+            template,
+            pending_space: false,
+        }
+    }
+
+    fn run(mut self) -> Vec<u8> {
+        while let Some(byte) = self.peek(0) {
+            if self.at_template_tag() {
+                self.flush_space();
+                self.template_tag();
+            } else if byte.is_ascii_whitespace() {
+                self.skip_whitespace();
+                // Trim whitespace at the start
+                self.pending_space = !self.out.is_empty();
+            } else if self.starts_with(b"<!--") {
+                self.flush_space();
+                self.copy_until(b"-->");
+            } else if byte == b'<' && self.peek(1).is_some_and(is_tag_start) {
+                self.flush_space();
+                if let Some(name) = self.tag() {
+                    self.raw_text(name);
+                }
+            } else {
+                self.flush_space();
+                self.copy_byte();
+            }
+        }
+        // Whitespace at the end is dropped with the pending space
+        self.out
+    }
+
+    /// Copy a start or end tag, starting at `<`. Returns the tag name if the tag starts
+    /// an element whose content should be copied as is.
+    fn tag(&mut self) -> Option<&'a [u8]> {
+        self.copy_byte();
+        let is_end = self.peek(0) == Some(b'/');
+        if is_end {
+            self.copy_byte();
+        }
+        let name_start = self.pos;
+        while self
+            .peek(0)
+            .is_some_and(|b| !b.is_ascii_whitespace() && b != b'>' && b != b'/')
+        {
+            self.copy_byte();
+        }
+        let name = &self.src[name_start..self.pos];
+
+        while let Some(byte) = self.peek(0) {
+            if self.at_template_tag() {
+                self.flush_space();
+                self.template_tag();
+            } else if byte.is_ascii_whitespace() {
+                self.skip_whitespace();
+                self.pending_space = true;
+            } else if byte == b'>' || self.starts_with(b"/>") {
+                self.pending_space = false;
+                let self_closing = byte == b'/';
+                self.copy_until(b">");
+                let is_raw = RAW_TEXT_ELEMENTS
+                    .iter()
+                    .any(|raw| raw.as_bytes().eq_ignore_ascii_case(name));
+                return (is_raw && !is_end && !self_closing).then_some(name);
+            } else if byte == b'=' {
+                self.pending_space = false;
+                self.copy_byte();
+                self.skip_whitespace();
+            } else if byte == b'"' || byte == b'\'' {
+                self.flush_space();
+                self.quoted();
+            } else {
+                self.flush_space();
+                self.copy_byte();
+            }
+        }
+        None
+    }
+
+    /// Copy the content of e.g. `<pre>` as is, up to its end tag.
+    fn raw_text(&mut self, name: &[u8]) {
+        while self.peek(0).is_some() {
+            if self.at_end_tag(name) {
+                return;
+            } else if self.at_template_tag() {
+                self.template_tag();
+            } else {
+                self.copy_byte();
+            }
+        }
+    }
+
+    /// Copy a quoted attribute value as is, including the quotes.
+    fn quoted(&mut self) {
+        let quote = self.src[self.pos];
+        self.copy_byte();
+        while let Some(byte) = self.peek(0) {
+            if self.at_template_tag() {
+                self.template_tag();
+            } else {
+                self.copy_byte();
+                if byte == quote {
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Copy `{{ }}` or `{% %}` tag with whitespace collapsed and trimmed. `{# #}` comments
+    /// and the content of `{% verbatim %}` blocks are copied as is.
+    fn template_tag(&mut self) {
+        let start = self.pos;
+        let close: &[u8] = match self.src[self.pos + 1] {
+            b'{' => b"}}",
+            b'%' => b"%}",
+            _ => return self.copy_until(b"#}"),
+        };
+        self.template_tag_until(close);
+
+        // The content is rendered as is, so whitespace in it matters
+        if close != b"%}" || self.pos < start + 4 || !self.src[..self.pos].ends_with(close) {
+            return;
+        }
+        let content = self.src[start + 2..self.pos - 2].trim_ascii();
+        if content == b"verbatim" || content.starts_with(b"verbatim ") {
+            let len = verbatim_len(&self.src[self.pos..], content);
+            self.copy_len(len);
+        }
+    }
+
+    fn template_tag_until(&mut self, close: &[u8]) {
+        self.copy_byte();
+        self.copy_byte();
+        self.skip_whitespace();
+
+        while let Some(byte) = self.peek(0) {
+            if self.starts_with(close) {
+                self.pending_space = false;
+                self.copy_byte();
+                self.copy_byte();
+                return;
+            } else if byte.is_ascii_whitespace() {
+                self.skip_whitespace();
+                self.pending_space = true;
+            } else if byte == b'"' || byte == b'\'' {
+                self.flush_space();
+                self.copy_byte();
+                // Unlike in attributes, a template tag can't be nested in the string
+                match self.src[self.pos..].iter().position(|&b| b == byte) {
+                    Some(len) => self.copy_len(len + 1),
+                    None => self.copy_len(self.src.len() - self.pos),
+                }
+            } else {
+                self.flush_space();
+                self.copy_byte();
+            }
+        }
+    }
+
+    fn at_template_tag(&self) -> bool {
+        self.template
+            && self.peek(0) == Some(b'{')
+            && matches!(self.peek(1), Some(b'{' | b'%' | b'#'))
+    }
+
+    fn at_end_tag(&self, name: &[u8]) -> bool {
+        let rest = &self.src[self.pos..];
+        rest.starts_with(b"</")
+            && rest.len() >= name.len() + 2
+            && rest[2..name.len() + 2].eq_ignore_ascii_case(name)
+            && rest
+                .get(name.len() + 2)
+                .is_none_or(|b| b.is_ascii_whitespace() || *b == b'>' || *b == b'/')
+    }
+
+    fn peek(&self, offset: usize) -> Option<u8> {
+        self.src.get(self.pos + offset).copied()
+    }
+
+    fn starts_with(&self, prefix: &[u8]) -> bool {
+        self.src[self.pos..].starts_with(prefix)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek(0).is_some_and(|b| b.is_ascii_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn flush_space(&mut self) {
+        if self.pending_space {
+            self.out.push(b' ');
+            self.pending_space = false;
+        }
+    }
+
+    fn copy_byte(&mut self) {
+        self.copy_len(1);
+    }
+
+    fn copy_len(&mut self, len: usize) {
+        self.out
+            .extend_from_slice(&self.src[self.pos..self.pos + len]);
+        self.pos += len;
+    }
+
+    /// Copy everything up to and including `end`, or the rest of the input if there is no `end`.
+    fn copy_until(&mut self, end: &[u8]) {
+        let len = self.src[self.pos..]
+            .windows(end.len())
+            .position(|window| window == end)
+            .map_or(self.src.len() - self.pos, |index| index + end.len());
+        self.copy_len(len);
+    }
+}
+
+/// Length of the content of a `{% verbatim %}` block, up to its end tag, e.g. `{% endverbatim %}`
+/// for `{% verbatim %}`, or `{% endverbatim name %}` for `{% verbatim name %}`. Same as Django,
+/// the end tag must have the same name, and the whitespace in the tag is trimmed.
+fn verbatim_len(src: &[u8], start_content: &[u8]) -> usize {
+    let mut pos = 0;
+    while let Some(offset) = src[pos..].windows(2).position(|w| w == b"{%") {
+        let tag_start = pos + offset;
+        let Some(len) = src[tag_start..].windows(2).position(|w| w == b"%}") else {
+            break;
+        };
+        let content = src[tag_start + 2..tag_start + len].trim_ascii();
+        if content.strip_prefix(b"end") == Some(start_content) {
+            return tag_start;
+        }
+        pos = tag_start + 2;
+    }
+    src.len()
+}
+
+fn is_tag_start(byte: u8) -> bool {
+    byte.is_ascii_alphabetic() || byte == b'/' || byte == b'!' || byte == b'?'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn normalize(src: &str, template: bool) -> String {
+        String::from_utf8(Normalizer::new(src, template).run()).unwrap()
+    }
+
+    #[test]
+    fn test_normalize_html() {
+        let input =
+            "\n  <div   class=\"a  b\"\n   id = 'x' >\n    Ahoj   světe\n  <br />\n</div >\n";
+        assert_eq!(
+            normalize(input, false),
+            "<div class=\"a  b\" id='x'> Ahoj světe <br/> </div>"
+        );
+
+        // Whitespace in comments and raw text elements is kept
+        let input = "<pre>\n  a\n   b</pre>  <!--  x  --> <script>let a  = 1;</script >";
+        assert_eq!(
+            normalize(input, false),
+            "<pre>\n  a\n   b</pre> <!--  x  --> <script>let a  = 1;</script>"
+        );
+
+        // Template tags are left alone in HTML mode
+        assert_eq!(normalize("<p>{{  a }}</p>", false), "<p>{{ a }}</p>");
+    }
+
+    #[test]
+    fn test_normalize_template() {
+        let input =
+            "{%  if x  %}<p class=\"{{ cls|default:'a  b' }}\">{{name}}</p>{%endif%}  {#  c  #}";
+        assert_eq!(
+            normalize(input, true),
+            "{%if x%}<p class=\"{{cls|default:'a  b'}}\">{{name}}</p>{%endif%} {#  c  #}"
+        );
+
+        // Quotes inside a template tag don't end the attribute value
+        assert_eq!(
+            normalize("<p title=\"{% trans  \"Hi\" %}\">", true),
+            "<p title=\"{%trans \"Hi\"%}\">"
+        );
+        assert_eq!(
+            normalize("<script>{{  data }}  </script>", true),
+            "<script>{{data}}  </script>"
+        );
+
+        // Content of verbatim blocks is rendered as is
+        assert_eq!(
+            normalize("{% verbatim %}\n{{ a  }}  <p>{% endverbatim %}  {{ b  }}", true),
+            "{%verbatim%}\n{{ a  }}  <p>{%endverbatim%} {{b}}"
+        );
+        assert_eq!(
+            normalize("{%verbatim x%}{% endverbatim %}{{ a  }}{%  endverbatim x %}", true),
+            "{%verbatim x%}{% endverbatim %}{{ a  }}{%endverbatim x%}"
+        );
+        assert_eq!(normalize("{% verbatim %} a  b", true), "{%verbatim%} a  b");
+        assert_eq!(normalize("a {%", true), "a {%");
+    }
+
+    #[test]
+    fn test_hash() {
+        assert_eq!(
+            hash_html("<div>\n  <p>Hi</p>\n</div>"),
+            hash_html("<div> <p>Hi</p> </div>")
+        );
+        assert_ne!(hash_html("<p>Hi</p>"), hash_html("<p>Hello</p>"));
+        assert_ne!(hash_html("<pre>a b</pre>"), hash_html("<pre>a  b</pre>"));
+
+        assert_eq!(hash_template("{{ name }}"), hash_template("{{name}}"));
+        assert_ne!(
+            hash_template("{% verbatim %}{{ a  }}{% endverbatim %}"),
+            hash_template("{% verbatim %}{{ a }}{% endverbatim %}")
+        );
+        assert_ne!(hash_html("{{ name }}"), hash_html("{{name}}"));
+    }
+}
@@ -0,0 +1,185 @@
+use quick_xml::events::BytesStart;
+
+/// `hx-*` attributes to add to the root elements, or to the elements that match the selectors,
+/// e.g. `hx-target` or `hx-headers` shared by all elements of a component
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct HtmxAttributes {
+    attributes: Vec<(String, String)>,
+    selectors: Vec<Selector>,
+}
+
+/// Simple selector, e.g. `button.primary[hx-get]`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+struct Selector {
+    tag: Option<String>,
+    id: Option<String>,
+    classes: Vec<String>,
+    attributes: Vec<String>,
+}
+
+impl HtmxAttributes {
+    /// Add the attributes, given as (name, value) pairs, to the root elements. Names must start
+    /// with `hx-` or `data-hx-`.
+    ///
+    /// With `selectors`, the attributes are added to the elements that match any of them instead.
+    /// Only simple selectors are supported, made of a tag name, `#id`, `.class` and `[attribute]`,
+    /// e.g. `form`, `.btn` or `a[href]`. Tag and attribute names are matched case-insensitively.
+    pub fn new(
+        attributes: Vec<(String, String)>,
+        selectors: &[String],
+    ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        if let Some((name, _)) = attributes
+            .iter()
+            .find(|(name, _)| !name.starts_with("hx-") && !name.starts_with("data-hx-"))
+        {
+            return Err(format!(
+                "HTMX attribute must start with 'hx-' or 'data-hx-', got '{name}'"
+            )
+            .into());
+        }
+        let selectors = selectors
+            .iter()
+            .map(|selector| Selector::parse(selector))
+            .collect::<Result<_, _>>()?;
+        Ok(HtmxAttributes {
+            attributes,
+            selectors,
+        })
+    }
+
+    pub(crate) fn attributes(&self) -> &[(String, String)] {
+        &self.attributes
+    }
+
+    /// Whether the attributes should be added to the element
+    pub(crate) fn applies_to(&self, name: &str, element: &BytesStart, is_root: bool) -> bool {
+        if self.selectors.is_empty() {
+            is_root
+        } else {
+            self.selectors.iter().any(|s| s.matches(name, element))
+        }
+    }
+}
+
+impl Selector {
+    fn parse(selector: &str) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let unsupported = || {
+            format!(
+                "Unsupported selector '{selector}', only tag names, '#id', '.class' and '[attribute]' are supported"
+            )
+        };
+        let mut parsed = Selector::default();
+        let mut rest = selector.trim();
+        if rest.is_empty() {
+            return Err(unsupported().into());
+        }
+
+        let tag_len = ident_len(rest);
+        if tag_len > 0 {
+            parsed.tag = Some(rest[..tag_len].to_string());
+            rest = &rest[tag_len..];
+        } else if let Some(after) = rest.strip_prefix('*') {
+            rest = after;
+        }
+        while let Some(prefix) = rest.chars().next() {
+            if !matches!(prefix, '#' | '.' | '[') {
+                return Err(unsupported().into());
+            }
+            let len = ident_len(&rest[1..]);
+            if len == 0 {
+                return Err(unsupported().into());
+            }
+            let ident = rest[1..len + 1].to_string();
+            rest = &rest[len + 1..];
+            match prefix {
+                '#' if parsed.id.is_none() => parsed.id = Some(ident),
+                '.' => parsed.classes.push(ident),
+                '[' => {
+                    rest = rest.strip_prefix(']').ok_or_else(unsupported)?;
+                    parsed.attributes.push(ident);
+                }
+                _ => return Err(unsupported().into()),
+            }
+        }
+        Ok(parsed)
+    }
+
+    fn matches(&self, name: &str, element: &BytesStart) -> bool {
+        if self
+            .tag
+            .as_ref()
+            .is_some_and(|tag| !tag.eq_ignore_ascii_case(name))
+        {
+            return false;
+        }
+        let attribute = |attr_name: &str| {
+            element
+                .html_attributes()
+                .flatten()
+                .find(|attr| attr.key.as_ref().eq_ignore_ascii_case(attr_name.as_bytes()))
+        };
+        if let Some(id) = &self.id {
+            if attribute("id").is_none_or(|attr| attr.value.as_ref() != id.as_bytes()) {
+                return false;
+            }
+        }
+        if !self.classes.is_empty() {
+            let Some(class) = attribute("class") else {
+                return false;
+            };
+            let class = String::from_utf8_lossy(&class.value);
+            let element_classes: Vec<&str> = class.split_ascii_whitespace().collect();
+            if !self
+                .classes
+                .iter()
+                .all(|c| element_classes.contains(&c.as_str()))
+            {
+                return false;
+            }
+        }
+        self.attributes.iter().all(|attr| attribute(attr).is_some())
+    }
+}
+
+/// Length of the name at the start of the text, e.g. a tag name or a class
+fn ident_len(text: &str) -> usize {
+    text.find(|c: char| !(c.is_alphanumeric() || c == '-' || c == '_' || c == ':'))
+        .unwrap_or(text.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_selector_matches() {
+        let element = BytesStart::from_content(r#"a id="x" class="btn  primary" hx-get="/""#, 1);
+        let matches = |selector: &str| Selector::parse(selector).unwrap().matches("a", &element);
+
+        assert!(matches("a"));
+        assert!(matches("A#x"));
+        assert!(matches("*.primary.btn"));
+        assert!(matches("[hx-get]"));
+        assert!(matches(".btn[HX-GET]"));
+        assert!(!matches("button"));
+        assert!(!matches("#y"));
+        assert!(!matches(".btn.secondary"));
+        assert!(!matches("a[href]"));
+    }
+
+    #[test]
+    fn test_invalid() {
+        for selector in ["", "div > p", "a, b", "[href=x]", ".", "a#x#y", "a→b"] {
+            let err = Selector::parse(selector).unwrap_err();
+            assert!(
+                err.to_string().starts_with("Unsupported selector"),
+                "{selector}"
+            );
+        }
+        let err = HtmxAttributes::new(vec![("class".into(), "x".into())], &[]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "HTMX attribute must start with 'hx-' or 'data-hx-', got 'class'"
+        );
+    }
+}
@@ -1,9 +1,30 @@
+use encoding::decode_html;
+use std::path::Path;
 use transformer::{transform};
 
+pub mod assets;
+pub mod batch;
+#[cfg(feature = "cache")]
+pub mod cache;
+pub mod encoding;
+pub mod fingerprint;
+pub mod htmx;
+mod output;
+pub mod source_map;
 pub mod transformer;
 
 // Re-export the types that users need
-pub use transformer::{HtmlTransformerConfig};
+pub use assets::{extract_html_assets, Asset, AssetKind};
+pub use batch::BatchResult;
+#[cfg(feature = "cache")]
+pub use cache::{CacheInfo, TransformCache};
+pub use fingerprint::{hash_html, hash_template};
+pub use htmx::HtmxAttributes;
+pub use source_map::{SourceMap, SourceMapping};
+pub use transformer::{
+    AttributeOrder, CapturedAttributes, HtmlTransformerConfig, TransformError, TransformResult,
+    TransformStats, TransformWarning,
+};
 
 /// Transform HTML by adding attributes to the elements.
 ///
@@ -15,11 +36,84 @@ pub use transformer::{HtmlTransformerConfig};
 ///
 /// Returns:
 ///     A Result containing either:
-///     - Ok((html, captured)): A tuple with the transformed HTML and captured attributes
+///     - Ok(result): The transformed HTML, captured attributes, and the source map if enabled
 ///     - Err(error): An error if the HTML is malformed or cannot be parsed.
 pub fn set_html_attributes(
     html: &str,
     config: &HtmlTransformerConfig,
-) -> Result<(String, Vec<(String, Vec<String>)>), Box<dyn std::error::Error>> {
-    transform(config, html)
+) -> Result<TransformResult, Box<dyn std::error::Error>> {
+    transform(config, html).map_err(|e| e as Box<dyn std::error::Error>)
+}
+
+/// Read a HTML file and transform it by adding attributes to the elements.
+///
+/// The file is decoded based on its byte order mark or `<meta charset>` declaration,
+/// and as UTF-8 otherwise. See [`encoding::decode_html`].
+///
+/// Args:
+///     path: Path to the HTML file.
+///     config: The HTML transformer configuration.
+///
+/// Returns:
+///     A Result containing either:
+///     - Ok(result): The transformed HTML, captured attributes, and the source map if enabled.
+///       Source map positions are byte offsets into the decoded HTML.
+///     - Err(error): An error if the file cannot be read, or the HTML is malformed.
+pub fn transform_html_file(
+    path: impl AsRef<Path>,
+    config: &HtmlTransformerConfig,
+) -> Result<TransformResult, Box<dyn std::error::Error>> {
+    let bytes = std::fs::read(path)?;
+    let (html, _encoding) = decode_html(&bytes);
+    transform(config, &html).map_err(|e| e as Box<dyn std::error::Error>)
+}
+
+/// Transform many HTML strings with the same configuration, one after another.
+///
+/// Args:
+///     htmls: The HTML strings to transform.
+///     config: The HTML transformer configuration.
+///
+/// Returns:
+///     One result per HTML string, in the same order as the inputs.
+pub fn set_html_attributes_many<S: AsRef<str>>(
+    htmls: &[S],
+    config: &HtmlTransformerConfig,
+) -> Vec<BatchResult> {
+    batch::transform_many(config, htmls)
+}
+
+/// Transform many HTML strings with the same configuration, in parallel on a rayon thread pool.
+///
+/// Args:
+///     htmls: The HTML strings to transform.
+///     config: The HTML transformer configuration.
+///     num_threads: Number of threads to use. Defaults to the number of CPUs.
+///
+/// Returns:
+///     A Result containing either:
+///     - Ok(results): One result per HTML string, in the same order as the inputs
+///     - Err(error): An error if the thread pool cannot be created.
+#[cfg(feature = "parallel")]
+pub fn set_html_attributes_many_parallel<S: AsRef<str> + Sync>(
+    htmls: &[S],
+    config: &HtmlTransformerConfig,
+    num_threads: Option<usize>,
+) -> Result<Vec<BatchResult>, Box<dyn std::error::Error>> {
+    Ok(batch::transform_many_parallel(config, htmls, num_threads)?)
+}
+
+/// Names of the optional cargo features that the crate was compiled with, e.g. `["cache", "parallel"]`.
+///
+/// Returns:
+///     The enabled features, in alphabetical order.
+pub fn enabled_features() -> Vec<&'static str> {
+    let mut features = Vec::new();
+    if cfg!(feature = "cache") {
+        features.push("cache");
+    }
+    if cfg!(feature = "parallel") {
+        features.push("parallel");
+    }
+    features
 }
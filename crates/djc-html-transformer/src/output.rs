@@ -0,0 +1,186 @@
+use quick_xml::events::Event;
+use quick_xml::writer::Writer;
+use std::cell::RefCell;
+use std::ops::Range;
+
+// Buffers larger than this are not kept for reuse, so that a single large page
+// doesn't keep the memory allocated for the lifetime of the thread
+const MAX_REUSED_SEGMENTS: usize = 4096;
+const MAX_REUSED_SCRATCH: usize = 64 * 1024;
+
+thread_local! {
+    // Buffers of the last finished `Output` on this thread, reused by the next one
+    static BUFFERS: RefCell<Buffers> = RefCell::new(Buffers::default());
+}
+
+#[derive(Default)]
+struct Buffers {
+    segments: Vec<Segment>,
+    scratch: Vec<u8>,
+}
+
+/// Part of the transformed HTML
+#[derive(Debug, PartialEq)]
+enum Segment {
+    /// Copied from the input HTML as is
+    Source(Range<usize>),
+    /// Produced by the transformation, e.g. a tag with added attributes
+    Inserted(String),
+}
+
+/// Transformed HTML, collected as ranges of the input plus the modified tags,
+/// and joined only once at the end.
+///
+/// Most of a page is text and unmodified tags, so this avoids building up a second
+/// growing copy of the page next to the input. The internal buffers are reused
+/// by the next `Output` on the same thread.
+pub(crate) struct Output<'a> {
+    source: &'a str,
+    segments: Vec<Segment>,
+    len: usize,
+    // Reused to serialize each event before it's compared to the input
+    scratch: Writer<Vec<u8>>,
+    // Whether the events are copied from the input without serializing them
+    passthrough: bool,
+}
+
+impl<'a> Output<'a> {
+    pub(crate) fn new(source: &'a str) -> Self {
+        let buffers = BUFFERS.try_with(RefCell::take).unwrap_or_default();
+        Output {
+            source,
+            segments: buffers.segments,
+            len: 0,
+            scratch: Writer::new(buffers.scratch),
+            passthrough: false,
+        }
+    }
+
+    /// Output that copies the written events from the input as they are, for when
+    /// nothing is modified
+    pub(crate) fn passthrough(source: &'a str) -> Self {
+        Output {
+            passthrough: true,
+            ..Self::new(source)
+        }
+    }
+
+    /// Length of the output so far, in bytes
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Write the event that was read from `source[range]`. Returns whether the written
+    /// content is identical to the input.
+    pub(crate) fn write_event(
+        &mut self,
+        event: Event,
+        range: Range<usize>,
+    ) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+        if self.passthrough {
+            self.push_source(range);
+            return Ok(true);
+        }
+
+        self.scratch.get_mut().clear();
+        self.scratch.write_event(event)?;
+        let written = self.scratch.get_ref();
+
+        if written[..] == self.source.as_bytes()[range.clone()] {
+            self.push_source(range);
+            Ok(true)
+        } else {
+            self.push_scratch()?;
+            Ok(false)
+        }
+    }
+
+    /// Write an event that is not in the input, e.g. an end tag of an unclosed element
+    pub(crate) fn insert_event(
+        &mut self,
+        event: Event,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.scratch.get_mut().clear();
+        self.scratch.write_event(event)?;
+        self.push_scratch()
+    }
+
+    fn push_scratch(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let inserted = std::str::from_utf8(self.scratch.get_ref())?.to_string();
+        self.len += inserted.len();
+        self.segments.push(Segment::Inserted(inserted));
+        Ok(())
+    }
+
+    fn push_source(&mut self, range: Range<usize>) {
+        self.len += range.len();
+        // Consecutive unmodified events are a single range of the input
+        if let Some(Segment::Source(last)) = self.segments.last_mut() {
+            if last.end == range.start {
+                last.end = range.end;
+                return;
+            }
+        }
+        self.segments.push(Segment::Source(range));
+    }
+
+    /// Join the parts into the transformed HTML
+    pub(crate) fn finish(self) -> String {
+        let mut html = String::with_capacity(self.len);
+        for segment in &self.segments {
+            match segment {
+                Segment::Source(range) => html.push_str(&self.source[range.clone()]),
+                Segment::Inserted(inserted) => html.push_str(inserted),
+            }
+        }
+
+        let mut segments = self.segments;
+        let mut scratch = self.scratch.into_inner();
+        if segments.capacity() <= MAX_REUSED_SEGMENTS && scratch.capacity() <= MAX_REUSED_SCRATCH {
+            segments.clear();
+            scratch.clear();
+            // Fails only while the thread is being destroyed
+            let _ = BUFFERS.try_with(|buffers| buffers.replace(Buffers { segments, scratch }));
+        }
+        html
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quick_xml::reader::Reader;
+
+    #[test]
+    fn test_output_segments() {
+        let source = "<p>Hi <!-- c --></p  >";
+        let mut reader = Reader::from_str(source);
+        let mut output = Output::new(source);
+        loop {
+            let start = reader.buffer_position() as usize;
+            let event = reader.read_event().unwrap();
+            if event == Event::Eof {
+                break;
+            }
+            let end = reader.buffer_position() as usize;
+            output.write_event(event, start..end).unwrap();
+        }
+
+        // The whitespace in the end tag is dropped, everything else is borrowed from the input
+        assert_eq!(
+            output.segments,
+            vec![
+                Segment::Source(0..16),
+                Segment::Inserted("</p>".to_string())
+            ]
+        );
+        assert_eq!(output.len(), 20);
+        assert_eq!(output.finish(), "<p>Hi <!-- c --></p>");
+
+        // The next output on the thread reuses the buffers
+        let output = Output::new(source);
+        assert!(output.segments.is_empty());
+        assert!(output.segments.capacity() >= 2);
+        assert!(output.scratch.get_ref().capacity() >= "</p>".len());
+    }
+}
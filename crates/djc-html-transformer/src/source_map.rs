@@ -0,0 +1,78 @@
+/// Maps a range of the transformed HTML back to the range of the input it was produced from.
+///
+/// All positions are byte offsets.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceMapping {
+    pub output_start: usize,
+    pub output_end: usize,
+    pub source_start: usize,
+    pub source_end: usize,
+}
+
+/// Source-map-like structure that maps ranges of the transformed HTML back to the input HTML.
+///
+/// Unchanged content (text, comments, untouched tags) that follows one another is merged
+/// into a single mapping, so the number of mappings is roughly the number of modified tags.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SourceMap {
+    pub mappings: Vec<SourceMapping>,
+    // Whether the last mapping is a verbatim copy of the source, so it can be extended
+    last_verbatim: bool,
+}
+
+impl SourceMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record that `output[output_start..output_end]` was produced from
+    /// `source[source_start..source_end]`. Set `verbatim` if the content is identical.
+    pub fn add(
+        &mut self,
+        output_start: usize,
+        output_end: usize,
+        source_start: usize,
+        source_end: usize,
+        verbatim: bool,
+    ) {
+        // E.g. end tags of void elements are dropped from the output
+        if output_start == output_end {
+            return;
+        }
+
+        if verbatim && self.last_verbatim {
+            if let Some(last) = self.mappings.last_mut() {
+                if last.output_end == output_start && last.source_end == source_start {
+                    last.output_end = output_end;
+                    last.source_end = source_end;
+                    return;
+                }
+            }
+        }
+
+        self.mappings.push(SourceMapping {
+            output_start,
+            output_end,
+            source_start,
+            source_end,
+        });
+        self.last_verbatim = verbatim;
+    }
+
+    /// Serialize as JSON, e.g. `{"version":1,"mappings":[[0,31,0,5]]}`, where each mapping
+    /// is `[output_start, output_end, source_start, source_end]`.
+    pub fn to_json(&self) -> String {
+        let mut json = String::from(r#"{"version":1,"mappings":["#);
+        for (i, m) in self.mappings.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            json.push_str(&format!(
+                "[{},{},{},{}]",
+                m.output_start, m.output_end, m.source_start, m.source_end
+            ));
+        }
+        json.push_str("]}");
+        json
+    }
+}
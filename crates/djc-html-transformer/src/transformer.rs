@@ -1,8 +1,14 @@
-use quick_xml::events::{BytesStart, Event};
+use quick_xml::events::attributes::AttrError;
+use quick_xml::events::{BytesEnd, BytesStart, BytesText, Event};
+use quick_xml::name::QName;
 use quick_xml::reader::Reader;
-use quick_xml::writer::Writer;
 use std::collections::HashSet;
-use std::io::Cursor;
+use std::hash::{Hash, Hasher};
+use std::ops::Range;
+
+use crate::htmx::HtmxAttributes;
+use crate::output::Output;
+use crate::source_map::{SourceMap, SourceMapping};
 
 // List of HTML5 void elements. These can be written as `<tag>` or `<tag />`,
 //e.g. `<br />`, `<link />`, `<img />`, etc.
@@ -11,13 +17,271 @@ const VOID_ELEMENTS: [&str; 14] = [
     "track", "wbr",
 ];
 
+// Elements that browsers reopen when they are closed by a misnested end tag,
+// e.g. `<b>` in `<b><i>Hi</b>there</i>`
+const FORMATTING_ELEMENTS: [&str; 14] = [
+    "a", "b", "big", "code", "em", "font", "i", "nobr", "s", "small", "strike", "strong", "tt", "u",
+];
+
+// Elements whose end tag may be omitted, e.g. `<li>` in `<ul><li>a<li>b</ul>`, so they are
+// not reported as unclosed
+const OPTIONAL_END_TAG_ELEMENTS: [&str; 21] = [
+    "body", "caption", "colgroup", "dd", "dt", "head", "html", "li", "optgroup", "option", "p",
+    "rb", "rp", "rt", "rtc", "tbody", "td", "tfoot", "th", "thead", "tr",
+];
+
+// Elements between which whitespace doesn't affect the layout, so it can be collapsed.
+// Includes the elements that are not displayed, e.g. `<meta>` or `<script>`.
+const BLOCK_ELEMENTS: [&str; 54] = [
+    "address",
+    "article",
+    "aside",
+    "base",
+    "blockquote",
+    "body",
+    "caption",
+    "col",
+    "colgroup",
+    "dd",
+    "details",
+    "dialog",
+    "div",
+    "dl",
+    "dt",
+    "fieldset",
+    "figcaption",
+    "figure",
+    "footer",
+    "form",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "head",
+    "header",
+    "hgroup",
+    "hr",
+    "html",
+    "li",
+    "link",
+    "main",
+    "menu",
+    "meta",
+    "nav",
+    "ol",
+    "p",
+    "pre",
+    "script",
+    "section",
+    "style",
+    "summary",
+    "table",
+    "tbody",
+    "td",
+    "template",
+    "tfoot",
+    "th",
+    "thead",
+    "title",
+    "tr",
+    "ul",
+];
+
+// Elements whose whitespace is kept when collapsing whitespace
+const PRESERVE_WHITESPACE_ELEMENTS: [&str; 4] = ["pre", "textarea", "script", "style"];
+
+// Prefixes of the attributes of frontend frameworks, e.g. Alpine's `@click` or `x-on:click.prevent`,
+// Vue's `:class` or `#default`, or HTMX's `hx-on::after-request`
+const DIRECTIVE_PREFIXES: [&str; 7] = ["@", ":", "#", "x-", "v-", "hx-", "data-hx-"];
+
+// Comments that are kept by default when stripping comments: conditional comments,
+// e.g. `<!--[if IE]>...<![endif]-->`, and the markers that django-components inserts
+const DEFAULT_KEPT_COMMENTS: [&str; 4] = ["[if *", "<![endif]*", "_RENDERED *", "djc-*"];
+
+// Name of the hidden input with the CSRF token that Django checks in POST requests
+const CSRF_FIELD_NAME: &str = "csrfmiddlewaretoken";
+
+/// Pairs of (watched attribute value, attributes added to that element)
+pub type CapturedAttributes = Vec<(String, Vec<String>)>;
+
+/// Order of the attributes of the elements that the transformer adds attributes to
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub enum AttributeOrder {
+    /// Existing attributes as they are, followed by the added ones
+    #[default]
+    Original,
+    /// Sorted by name, case-insensitively
+    Alphabetical,
+    /// The listed attributes first, in the order of the list, followed by the rest
+    /// in the original order
+    Priority(Vec<String>),
+}
+
 /// Configuration for HTML transformation
+#[derive(Clone, PartialEq, Eq)]
 pub struct HtmlTransformerConfig {
     root_attributes: Vec<String>,
     all_attributes: Vec<String>,
     void_elements: HashSet<String>,
     check_end_names: bool,
     watch_on_attribute: Option<String>,
+    source_map: bool,
+    attribute_order: AttributeOrder,
+    repair_misnested: bool,
+    xml: bool,
+    csrf_token: Option<String>,
+    htmx: Option<HtmxAttributes>,
+    preserve_directives: bool,
+    strip_comments: bool,
+    kept_comments: Vec<String>,
+    collapse_whitespace: bool,
+}
+
+/// Result of the HTML transformation
+#[derive(Debug, Clone)]
+pub struct TransformResult {
+    /// The transformed HTML
+    pub html: String,
+    /// Attributes added to the elements with the watched attribute
+    pub captured: CapturedAttributes,
+    /// Mapping of the transformed HTML back to the input. Only set if enabled in the config.
+    pub source_map: Option<SourceMap>,
+    /// Problems in the input that were worked around, e.g. unclosed tags
+    pub warnings: Vec<TransformWarning>,
+    /// Names of the root elements, lowercased unless in XML mode, in the order of appearance
+    pub roots: Vec<String>,
+    pub stats: TransformStats,
+}
+
+impl TransformResult {
+    /// Approximate number of bytes that the result holds on the heap, e.g. to size
+    /// a cache of results. Doesn't include the size of the struct itself.
+    pub fn heap_size(&self) -> usize {
+        let strings = |strings: &[String]| -> usize { strings.iter().map(String::capacity).sum() };
+
+        let captured: usize = self
+            .captured
+            .iter()
+            .map(|(value, attrs)| {
+                value.capacity() + attrs.capacity() * std::mem::size_of::<String>() + strings(attrs)
+            })
+            .sum();
+        let source_map = self.source_map.as_ref().map_or(0, |source_map| {
+            source_map.mappings.capacity() * std::mem::size_of::<SourceMapping>()
+        });
+        let warnings: usize = self.warnings.iter().map(|w| w.message.capacity()).sum();
+
+        self.html.capacity()
+            + self.captured.capacity() * std::mem::size_of::<(String, Vec<String>)>()
+            + captured
+            + source_map
+            + self.warnings.capacity() * std::mem::size_of::<TransformWarning>()
+            + warnings
+            + self.roots.capacity() * std::mem::size_of::<String>()
+            + strings(&self.roots)
+    }
+}
+
+/// Counts of what the transformation did
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TransformStats {
+    /// Number of elements in the HTML, excluding end tags
+    pub elements: usize,
+    /// Number of attributes added to all elements together
+    pub attributes_added: usize,
+}
+
+/// Problem in the input HTML that didn't prevent the transformation, e.g. an unclosed tag
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransformWarning {
+    pub message: String,
+    /// Byte offset into the input HTML
+    pub offset: usize,
+    /// 1-based line and column (in characters) of the offset
+    pub line: usize,
+    pub column: usize,
+}
+
+impl TransformWarning {
+    fn new(html: &str, offset: usize, message: String) -> Self {
+        let (line, column) = line_column(html, offset);
+        TransformWarning {
+            message,
+            offset,
+            line,
+            column,
+        }
+    }
+}
+
+impl std::fmt::Display for TransformWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} at line {}, column {}",
+            self.message, self.line, self.column
+        )
+    }
+}
+
+/// Problem in the input HTML that prevented the transformation, e.g. a malformed tag
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransformError {
+    pub message: String,
+    /// Byte offset into the input HTML
+    pub offset: usize,
+    /// 1-based line and column (in characters) of the offset
+    pub line: usize,
+    pub column: usize,
+    /// The line with the problem, shortened to at most 80 characters around the offset
+    pub snippet: String,
+}
+
+impl TransformError {
+    pub(crate) fn new(html: &str, offset: usize, message: String) -> Self {
+        let offset = offset.min(html.len());
+        let (line, column) = line_column(html, offset);
+        let line_start = html[..offset].rfind('\n').map_or(0, |idx| idx + 1);
+        let line_end = html[offset..]
+            .find('\n')
+            .map_or(html.len(), |idx| offset + idx);
+        let snippet: String = html[line_start..line_end]
+            .chars()
+            .skip(column.saturating_sub(41))
+            .take(80)
+            .collect();
+        TransformError {
+            message,
+            offset,
+            line,
+            column,
+            snippet: snippet.trim_end_matches('\r').to_string(),
+        }
+    }
+}
+
+impl std::fmt::Display for TransformError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} at line {}, column {}",
+            self.message, self.line, self.column
+        )
+    }
+}
+
+impl std::error::Error for TransformError {}
+
+/// 1-based line and column (in characters) of the byte offset into the text
+fn line_column(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[..offset];
+    let line_start = before.rfind('\n').map_or(0, |idx| idx + 1);
+    (
+        before.matches('\n').count() + 1,
+        before[line_start..].chars().count() + 1,
+    )
 }
 
 impl HtmlTransformerConfig {
@@ -35,31 +299,206 @@ impl HtmlTransformerConfig {
             void_elements,
             check_end_names,
             watch_on_attribute,
+            source_map: false,
+            attribute_order: AttributeOrder::Original,
+            repair_misnested: false,
+            xml: false,
+            csrf_token: None,
+            htmx: None,
+            preserve_directives: false,
+            strip_comments: false,
+            kept_comments: DEFAULT_KEPT_COMMENTS
+                .iter()
+                .map(|&s| s.to_string())
+                .collect(),
+            collapse_whitespace: false,
         }
     }
+
+    /// Record which ranges of the output were produced from which ranges of the input
+    pub fn with_source_map(mut self, enabled: bool) -> Self {
+        self.source_map = enabled;
+        self
+    }
+
+    /// Emit the attributes of the elements in the given order, so that the same element
+    /// is always written the same way
+    pub fn with_attribute_order(mut self, order: AttributeOrder) -> Self {
+        self.attribute_order = order;
+        self
+    }
+
+    /// Repair misnested and unclosed tags the way browsers do, so that the output matches
+    /// the DOM that the browser builds. Otherwise the tags are kept as they are.
+    ///
+    /// Unclosed elements are closed, and unexpected end tags are removed. Formatting elements,
+    /// e.g. `<b>`, that are closed by a misnested end tag are reopened before the next content,
+    /// so `<b><i>1</b>2</i>` becomes `<b><i>1</i></b><i>2</i>`. Unlike in browsers, other elements
+    /// inside formatting elements, e.g. `<p>` in `<b><p>1</b>2</p>`, are closed, not moved.
+    ///
+    /// Has no effect with `check_end_names`, which rejects misnested tags.
+    pub fn with_repair_misnested(mut self, enabled: bool) -> Self {
+        self.repair_misnested = enabled;
+        self
+    }
+
+    /// Parse the input as XML, e.g. SVG files or XML partials, instead of HTML.
+    ///
+    /// Tag names are case-sensitive and kept as they are, e.g. in `roots`. There are no void
+    /// elements, so empty elements must be self-closing, e.g. `<br/>`, or closed with an end tag,
+    /// e.g. `<link>...</link>` in RSS. Misnested and unclosed tags are errors, same as with
+    /// `check_end_names`, so `repair_misnested` has no effect. CDATA sections and processing
+    /// instructions are kept as they are in both modes.
+    pub fn with_xml(mut self, enabled: bool) -> Self {
+        self.xml = enabled;
+        self
+    }
+
+    /// Insert a hidden input with the CSRF token, e.g. `<input type="hidden" name="csrfmiddlewaretoken"
+    /// value="...">`, into every `<form method="post">` that doesn't have it yet. The input is
+    /// inserted before the end of the form, so the inputs already in the form can be checked first.
+    pub fn with_csrf_token(mut self, token: Option<String>) -> Self {
+        self.csrf_token = token;
+        self
+    }
+
+    /// Add the `hx-*` attributes to the root elements, or to the elements that match
+    /// the selectors, along with the other attributes. Attributes that the element already has
+    /// are not added, so the element can override them.
+    pub fn with_htmx(mut self, htmx: Option<HtmxAttributes>) -> Self {
+        self.htmx = htmx;
+        self
+    }
+
+    /// Keep the attributes of frontend frameworks, e.g. `@click`, `:class`, `x-on:click.prevent`
+    /// or `hx-on::after-request`, exactly as they are written when the attributes are rewritten
+    /// in the `attribute_order`. Otherwise they are written as `name="value"` like other attributes.
+    ///
+    /// Attributes are never rewritten with the original order, so this has no effect then.
+    pub fn with_preserve_directives(mut self, enabled: bool) -> Self {
+        self.preserve_directives = enabled;
+        self
+    }
+
+    /// Remove HTML comments, except those that match the patterns set with `with_kept_comments()`
+    pub fn with_strip_comments(mut self, enabled: bool) -> Self {
+        self.strip_comments = enabled;
+        self
+    }
+
+    /// Patterns of the comments to keep when stripping comments. `*` matches any text, and
+    /// the patterns are matched against the content of the comment without the surrounding
+    /// whitespace, e.g. `[if IE]>...<![endif]` for `<!--[if IE]>...<![endif]-->`.
+    ///
+    /// Defaults to conditional comments and django-components markers: `[if *`, `<![endif]*`,
+    /// `_RENDERED *` and `djc-*`.
+    pub fn with_kept_comments(mut self, patterns: Vec<String>) -> Self {
+        self.kept_comments = patterns;
+        self
+    }
+
+    /// Collapse whitespace between block-level tags, e.g. the indentation between `</li>`
+    /// and `<li>`, into a single line break, or a space if there is no line break.
+    ///
+    /// Whitespace next to text or inline elements, e.g. between `</span>` and `<span>`,
+    /// and inside `<pre>`, `<textarea>`, `<script>` and `<style>` is kept as it is.
+    pub fn with_collapse_whitespace(mut self, enabled: bool) -> Self {
+        self.collapse_whitespace = enabled;
+        self
+    }
+
+    /// Whether the comment with the given content is removed
+    fn strips_comment(&self, content: &str) -> bool {
+        let content = content.trim();
+        self.strip_comments
+            && !self
+                .kept_comments
+                .iter()
+                .any(|pattern| glob_match(pattern, content))
+    }
+
+    /// Name of the element as it's compared to other names. HTML names are case-insensitive.
+    fn element_name(&self, name: QName) -> String {
+        let name = String::from_utf8_lossy(name.as_ref());
+        if self.xml {
+            name.into_owned()
+        } else {
+            name.to_lowercase()
+        }
+    }
+
+    /// Whether the element can't have content, e.g. `<br>`. XML has no void elements.
+    fn is_void(&self, name: &str) -> bool {
+        !self.xml && self.void_elements.contains(name)
+    }
+
+    /// Whether an unclosed element is a recovered error worth a warning. Elements with optional
+    /// end tags, e.g. `<li>` or `<p>`, are valid HTML also when not closed.
+    fn warns_unclosed(&self, element: &OpenElement) -> bool {
+        !element.reopened
+            && (self.xml || !OPTIONAL_END_TAG_ELEMENTS.contains(&element.name.as_str()))
+    }
+
+    /// Whether the transformation only checks the HTML, because there are no attributes
+    /// to add and no attribute to watch
+    fn is_check_only(&self) -> bool {
+        self.root_attributes.is_empty()
+            && self.all_attributes.is_empty()
+            && self.watch_on_attribute.is_none()
+            && !self.repair_misnested
+            && self.csrf_token.is_none()
+            && self.htmx.is_none()
+            && !self.strip_comments
+            && !self.collapse_whitespace
+    }
+}
+
+// Two configs with the same hash produce the same output for the same HTML,
+// so the hash can be used as part of a cache key.
+impl Hash for HtmlTransformerConfig {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // Void elements are the same for all configs
+        self.root_attributes.hash(state);
+        self.all_attributes.hash(state);
+        self.check_end_names.hash(state);
+        self.watch_on_attribute.hash(state);
+        self.source_map.hash(state);
+        self.attribute_order.hash(state);
+        self.repair_misnested.hash(state);
+        self.xml.hash(state);
+        self.csrf_token.hash(state);
+        self.htmx.hash(state);
+        self.preserve_directives.hash(state);
+        self.strip_comments.hash(state);
+        self.kept_comments.hash(state);
+        self.collapse_whitespace.hash(state);
+    }
 }
 
-/// Add attributes to a HTML start tag (e.g. `<div>`) based on the configuration
+/// Add attributes to a HTML start tag (e.g. `<div>`) based on the configuration.
+/// Returns the number of added attributes.
 fn add_attributes(
     config: &HtmlTransformerConfig,
     element: &mut BytesStart,
     is_root: bool,
-    captured_attributes: &mut Vec<(String, Vec<String>)>,
-) {
-    let mut added_attrs = Vec::new();
+    captured_attributes: &mut CapturedAttributes,
+) -> usize {
+    let root_attributes: &[String] = if is_root {
+        &config.root_attributes
+    } else {
+        &[]
+    };
 
-    // Add root attributes if this is a root element
-    if is_root {
-        for attr in &config.root_attributes {
-            element.push_attribute((attr.as_str(), ""));
-            added_attrs.push(attr.clone());
-        }
-    }
+    // Match the selectors before the element has the added attributes
+    let htmx = config
+        .htmx
+        .as_ref()
+        .filter(|htmx| htmx.applies_to(&config.element_name(element.name()), element, is_root));
 
-    // Add attributes that should be applied to all elements
-    for attr in &config.all_attributes {
+    // Add root attributes if this is a root element, and attributes that should be applied
+    // to all elements
+    for attr in root_attributes.iter().chain(&config.all_attributes) {
         element.push_attribute((attr.as_str(), ""));
-        added_attrs.push(attr.clone());
     }
 
     // If we're watching for a specific attribute, check if this element has it
@@ -76,81 +515,590 @@ fn add_attributes(
             .and_then(|a| a.ok())
             .map(|a| String::from_utf8_lossy(a.value.as_ref()).into_owned())
         {
+            // Only the captured elements need the list of the added attributes
+            let added_attrs = root_attributes
+                .iter()
+                .chain(&config.all_attributes)
+                .cloned()
+                .collect();
             captured_attributes.push((attr_value, added_attrs));
         }
     }
+    let mut added = root_attributes.len() + config.all_attributes.len();
+    if let Some(htmx) = htmx {
+        added += add_htmx_attributes(htmx, element);
+    }
+    // Only the elements that got attributes are rewritten, the others are kept as they are
+    if added > 0 && config.attribute_order != AttributeOrder::Original {
+        sort_attributes(element, &config.attribute_order, config.preserve_directives);
+    }
+    added
+}
+
+/// Add the `hx-*` attributes that the element doesn't have yet. Returns the number
+/// of added attributes.
+fn add_htmx_attributes(htmx: &HtmxAttributes, element: &mut BytesStart) -> usize {
+    let existing: Vec<Vec<u8>> = element
+        .html_attributes()
+        .flatten()
+        .map(|attr| attr.key.as_ref().to_ascii_lowercase())
+        .collect();
+    let mut added = 0;
+    for (attr, value) in htmx.attributes() {
+        if !existing.contains(&attr.to_ascii_lowercase().into_bytes()) {
+            element.push_attribute((attr.as_str(), value.as_str()));
+            added += 1;
+        }
+    }
+    added
+}
+
+/// Rewrite the attributes of the element in the given order. Attributes are written
+/// as `name="value"`, so e.g. `disabled` becomes `disabled=""`, unless they are
+/// framework directives that should be kept as they are written.
+fn sort_attributes(element: &mut BytesStart, order: &AttributeOrder, preserve_directives: bool) {
+    let Ok(mut attributes) = element
+        .html_attributes()
+        .map(|attr| {
+            attr.map(|attr| {
+                let name = attr.key.as_ref();
+                let raw = (preserve_directives && is_directive(name))
+                    .then(|| raw_attribute(element, name).to_vec());
+                (name.to_vec(), attr.value.into_owned(), raw)
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()
+    else {
+        // Leave malformed attributes as they are
+        return;
+    };
+
+    match order {
+        AttributeOrder::Original => return,
+        AttributeOrder::Alphabetical => {
+            attributes.sort_by_key(|(name, _, _)| name.to_ascii_lowercase());
+        }
+        AttributeOrder::Priority(priority) => attributes.sort_by_key(|(name, _, _)| {
+            priority
+                .iter()
+                .position(|p| p.as_bytes().eq_ignore_ascii_case(name))
+                .unwrap_or(priority.len())
+        }),
+    }
+
+    let name_len = element.name().as_ref().len();
+    let mut content = element.name().as_ref().to_vec();
+    for (name, value, raw) in attributes {
+        content.push(b' ');
+        if let Some(raw) = raw {
+            content.extend_from_slice(&raw);
+            continue;
+        }
+        content.extend_from_slice(&name);
+        content.extend_from_slice(b"=\"");
+        // Values in single quotes can contain double quotes
+        if value.contains(&b'"') {
+            let value = String::from_utf8_lossy(&value).replace('"', "&quot;");
+            content.extend_from_slice(value.as_bytes());
+        } else {
+            content.extend_from_slice(&value);
+        }
+        content.push(b'"');
+    }
+    // The input is a string, so the attributes are valid UTF-8
+    *element = BytesStart::from_content(String::from_utf8_lossy(&content).into_owned(), name_len);
+}
+
+/// Whether the event is a block-level tag or the end of the HTML, next to which
+/// whitespace can be collapsed
+fn is_block_boundary(event: &Event) -> bool {
+    let name = match event {
+        Event::Start(e) | Event::Empty(e) => e.name(),
+        Event::End(e) => e.name(),
+        Event::Eof => return true,
+        _ => return false,
+    };
+    BLOCK_ELEMENTS
+        .iter()
+        .any(|block| block.as_bytes().eq_ignore_ascii_case(name.as_ref()))
+}
+
+/// Whether whitespace is significant inside the open elements, e.g. inside `<pre>`
+fn preserves_whitespace(open_elements: &[OpenElement]) -> bool {
+    open_elements
+        .iter()
+        .any(|el| PRESERVE_WHITESPACE_ELEMENTS.contains(&el.name.to_lowercase().as_str()))
+}
+
+/// Write the whitespace between block-level tags, collapsed if it's followed by
+/// a block-level tag too. Returns whether the output is identical to the input.
+fn write_whitespace(
+    output: &mut Output,
+    html: &str,
+    range: Range<usize>,
+    collapse: bool,
+) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+    let whitespace = &html[range.clone()];
+    let collapsed = if whitespace.contains('\n') { "\n" } else { " " };
+    if collapse && whitespace != collapsed {
+        output.insert_event(Event::Text(BytesText::from_escaped(collapsed)))?;
+        Ok(false)
+    } else {
+        output.write_event(Event::Text(BytesText::from_escaped(whitespace)), range)
+    }
+}
+
+/// Whether the text matches the pattern, where `*` matches any text, e.g. `[if *`
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    // Without `*`, the only part must match the whole text
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+    let mut parts: Vec<&str> = parts.collect();
+    let Some(last) = parts.pop() else {
+        return rest.is_empty();
+    };
+    for part in parts {
+        match rest.find(part) {
+            Some(idx) => rest = &rest[idx + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+/// Whether the attribute belongs to a frontend framework, e.g. `@click` or `hx-on::after-request`
+fn is_directive(name: &[u8]) -> bool {
+    DIRECTIVE_PREFIXES
+        .iter()
+        .any(|prefix| name.starts_with(prefix.as_bytes()))
+}
+
+/// The attribute with the given name as written in the tag, e.g. `x-on:click.prevent`
+/// or `:class='{ open: isOpen }'`. `name` must be borrowed from the element.
+fn raw_attribute<'a>(element: &'a BytesStart, name: &[u8]) -> &'a [u8] {
+    let content: &[u8] = element;
+    let start = name.as_ptr() as usize - content.as_ptr() as usize;
+    let mut end = start + name.len();
+
+    let skip_whitespace = |pos: usize| {
+        content[pos..]
+            .iter()
+            .position(|b| !b.is_ascii_whitespace())
+            .map_or(content.len(), |idx| pos + idx)
+    };
+    let eq = skip_whitespace(end);
+    if content.get(eq) == Some(&b'=') {
+        let value_start = skip_whitespace(eq + 1);
+        end = match content.get(value_start) {
+            Some(&quote @ (b'"' | b'\'')) => content[value_start + 1..]
+                .iter()
+                .position(|&b| b == quote)
+                .map_or(content.len(), |idx| value_start + idx + 2),
+            _ => content[value_start..]
+                .iter()
+                .position(|b| b.is_ascii_whitespace())
+                .map_or(content.len(), |idx| value_start + idx),
+        };
+    }
+    &content[start..end]
+}
+
+/// Element that is not closed yet
+struct OpenElement {
+    name: String,
+    /// Position of the start tag in the input
+    offset: usize,
+    /// Start tag as written, to reopen formatting elements when repairing misnested tags
+    start: Option<BytesStart<'static>>,
+    /// Whether the element was reopened, and so already reported as unclosed
+    reopened: bool,
+    /// Whether the element is a POST form without the CSRF token
+    needs_csrf: bool,
+}
+
+/// Reopen the formatting elements that were closed by a misnested end tag, same as browsers
+/// do before the next content
+fn reopen_elements(
+    output: &mut Output,
+    reopen: &mut Vec<OpenElement>,
+    open_elements: &mut Vec<OpenElement>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    for element in reopen.drain(..) {
+        if let Some(start) = &element.start {
+            output.insert_event(Event::Start(start.borrow()))?;
+        }
+        open_elements.push(element);
+    }
+    Ok(())
+}
+
+/// Whether the element is a form that is submitted with POST
+fn is_post_form(name: &str, element: &BytesStart) -> bool {
+    name == "form"
+        && element.html_attributes().flatten().any(|attr| {
+            attr.key.as_ref().eq_ignore_ascii_case(b"method")
+                && attr.value.trim_ascii().eq_ignore_ascii_case(b"post")
+        })
+}
+
+/// Whether the element is the hidden input with the CSRF token
+fn is_csrf_input(name: &str, element: &BytesStart) -> bool {
+    name == "input"
+        && element.html_attributes().flatten().any(|attr| {
+            attr.key.as_ref().eq_ignore_ascii_case(b"name")
+                && attr.value.as_ref() == CSRF_FIELD_NAME.as_bytes()
+        })
+}
+
+/// Note that the innermost open form already has the CSRF token
+fn mark_csrf_found(open_elements: &mut [OpenElement]) {
+    if let Some(form) = open_elements.iter_mut().rev().find(|el| el.name == "form") {
+        form.needs_csrf = false;
+    }
+}
+
+/// Insert the hidden input with the CSRF token, if the form that is being closed doesn't have it
+fn insert_csrf_input(
+    output: &mut Output,
+    closed: &[OpenElement],
+    token: &Option<String>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    if let Some(token) = token {
+        if closed.iter().any(|el| el.needs_csrf) {
+            let mut input = BytesStart::new("input");
+            input.push_attribute(("type", "hidden"));
+            input.push_attribute(("name", CSRF_FIELD_NAME));
+            input.push_attribute(("value", token.as_str()));
+            output.insert_event(Event::Empty(input))?;
+        }
+    }
+    Ok(())
+}
+
+/// Whether the event is an element or content before which browsers reopen
+/// the formatting elements
+fn is_content(event: &Event) -> bool {
+    match event {
+        Event::Text(text) => text.iter().any(|b| !b.is_ascii_whitespace()),
+        Event::Start(_) | Event::Empty(_) | Event::CData(_) | Event::GeneralRef(_) => true,
+        _ => false,
+    }
+}
+
+/// Warn about attributes that are set more than once in the same tag, e.g. `<div class="a" class="b">`
+fn check_duplicate_attributes(
+    html: &str,
+    element: &BytesStart,
+    offset: usize,
+    warnings: &mut Vec<TransformWarning>,
+) {
+    for attr in element.attributes().with_checks(true) {
+        if let Err(AttrError::Duplicated(pos, _)) = attr {
+            let rest = &element.as_ref()[pos..];
+            let name_len = rest
+                .iter()
+                .position(|&b| b == b'=' || b.is_ascii_whitespace())
+                .unwrap_or(rest.len());
+            let message = format!(
+                "Duplicate attribute '{}' in <{}>",
+                String::from_utf8_lossy(&rest[..name_len]),
+                String::from_utf8_lossy(element.name().as_ref())
+            );
+            warnings.push(TransformWarning::new(html, offset, message));
+        }
+    }
 }
 
 /// Main entrypoint. Transform HTML by adding attributes to the elements.
+///
+/// If there are no attributes to add and no attribute to watch, the HTML is only checked
+/// and returned as it is, e.g. `<br>` is not rewritten to `<br/>`. Only end tags of void
+/// elements, e.g. `</br>`, are still removed.
 pub fn transform(
     config: &HtmlTransformerConfig,
     html: &str,
-) -> Result<(String, Vec<(String, Vec<String>)>), Box<dyn std::error::Error>> {
+) -> Result<TransformResult, Box<dyn std::error::Error + Send + Sync>> {
+    let _span = tracing::debug_span!("transform_html", html_len = html.len()).entered();
+
     let mut reader = Reader::from_str(html);
     let reader_config = reader.config_mut();
-    reader_config.check_end_names = config.check_end_names;
+    // XML doesn't allow misnested end tags
+    reader_config.check_end_names = config.check_end_names || config.xml;
     // Allow bare & in HTML content (e.g. "Hello & Welcome" instead of requiring "Hello &amp; Welcome")
     // This is needed for compatibility with HTML5 which is more lenient than strict XML
     reader_config.allow_dangling_amp = true;
+    // Stray end tags are removed when repairing misnested tags
+    reader_config.allow_unmatched_ends = config.repair_misnested;
 
-    // We transform the HTML by reading it and writing it simultaneously
-    let mut writer = Writer::new(Cursor::new(Vec::new()));
+    // We transform the HTML by reading it and writing it simultaneously.
+    // Unmodified content is not copied until the end, see `Output`.
+    let mut output = if config.is_check_only() {
+        Output::passthrough(html)
+    } else {
+        Output::new(html)
+    };
     let mut captured_attributes = Vec::new();
 
+    let mut source_map = config.source_map.then(SourceMap::new);
+
     // Track the nesting depth of elements to identify root elements (depth == 0)
     let mut depth: i32 = 0;
 
+    // Elements that are not closed yet, to report misnested tags
+    let mut open_elements: Vec<OpenElement> = Vec::new();
+    // Formatting elements to reopen before the next content, when repairing misnested tags
+    let mut reopen: Vec<OpenElement> = Vec::new();
+    let mut warnings = Vec::new();
+    let mut roots = Vec::new();
+    let mut stats = TransformStats::default();
+    // Whitespace after a block-level tag, written once it's known what follows it
+    let mut pending_whitespace: Option<Range<usize>> = None;
+    // Whether the last event was a block-level tag, or the start of the HTML
+    let mut after_block = true;
+    // Whether the last event was a removed comment with whitespace before it, so the whitespace
+    // after it is dropped, e.g. `<p> <!-- c --> Hi` becomes `<p> Hi`
+    let mut merge_whitespace = false;
+
     // Read the HTML event by event
     loop {
-        match reader.read_event() {
+        let source_start = reader.buffer_position() as usize;
+        let mut output_start = output.len();
+        // Whether the output of this event is identical to the input
+        let mut verbatim = false;
+        let mut eof = false;
+        let after_removed_comment = std::mem::take(&mut merge_whitespace);
+
+        let event = reader.read_event();
+        let source = source_start..reader.buffer_position() as usize;
+        let block_boundary = event.as_ref().is_ok_and(is_block_boundary);
+        if let Some(whitespace) = pending_whitespace.take() {
+            let verbatim = write_whitespace(&mut output, html, whitespace.clone(), block_boundary)?;
+            if let Some(source_map) = source_map.as_mut() {
+                let verbatim = verbatim && output.len() - output_start == whitespace.len();
+                source_map.add(
+                    output_start,
+                    output.len(),
+                    whitespace.start,
+                    whitespace.end,
+                    verbatim,
+                );
+            }
+            output_start = output.len();
+        }
+        if config.repair_misnested && !reopen.is_empty() && event.as_ref().is_ok_and(is_content) {
+            reopen_elements(&mut output, &mut reopen, &mut open_elements)?;
+            depth = open_elements.len() as i32;
+        }
+
+        match event {
             // Start tag
             Ok(Event::Start(e)) => {
-                let tag_name = String::from_utf8_lossy(e.name().as_ref())
-                    .to_string()
-                    .to_lowercase();
+                let tag_name = config.element_name(e.name());
+                check_duplicate_attributes(html, &e, source_start, &mut warnings);
+                if depth == 0 {
+                    roots.push(tag_name.clone());
+                }
+                if config.csrf_token.is_some() && is_csrf_input(&tag_name, &e) {
+                    mark_csrf_found(&mut open_elements);
+                }
+                let needs_csrf = config.csrf_token.is_some() && is_post_form(&tag_name, &e);
                 let mut elem = e.into_owned();
-                add_attributes(config, &mut elem, depth == 0, &mut captured_attributes);
+                stats.elements += 1;
+                stats.attributes_added +=
+                    add_attributes(config, &mut elem, depth == 0, &mut captured_attributes);
 
                 // For void elements, write as Empty event
-                if config.void_elements.contains(&tag_name) {
-                    writer.write_event(Event::Empty(elem))?;
+                if config.is_void(&tag_name) {
+                    verbatim = output.write_event(Event::Empty(elem), source)?;
                 } else {
-                    writer.write_event(Event::Start(elem))?;
+                    let is_formatting = FORMATTING_ELEMENTS.contains(&tag_name.as_str());
+                    let start = (config.repair_misnested && is_formatting).then(|| elem.clone());
+                    verbatim = output.write_event(Event::Start(elem), source)?;
                     depth += 1;
+                    open_elements.push(OpenElement {
+                        name: tag_name,
+                        offset: source_start,
+                        start,
+                        reopened: false,
+                        needs_csrf,
+                    });
                 }
             }
 
             // End tag
             Ok(Event::End(e)) => {
-                let tag_name = String::from_utf8_lossy(e.name().as_ref())
-                    .to_string()
-                    .to_lowercase();
+                let tag_name = config.element_name(e.name());
 
                 // Skip end tags for void elements
-                if !config.void_elements.contains(&tag_name) {
-                    writer.write_event(Event::End(e))?;
-                    depth -= 1;
+                if !config.is_void(&tag_name) {
+                    // Without `check_end_names`, misnested end tags are accepted as they are,
+                    // unless they are repaired.
+                    // Same as browsers, an end tag closes also the unclosed elements inside it.
+                    let open_idx = open_elements.iter().rposition(|el| el.name == tag_name);
+                    match open_idx {
+                        Some(idx) => {
+                            insert_csrf_input(
+                                &mut output,
+                                &open_elements[idx..],
+                                &config.csrf_token,
+                            )?;
+                            let unclosed: Vec<OpenElement> =
+                                open_elements.drain(idx + 1..).collect();
+                            for element in unclosed.iter().filter(|el| config.warns_unclosed(el)) {
+                                let message = format!("Unclosed tag <{}>", element.name);
+                                warnings.push(TransformWarning::new(html, element.offset, message));
+                            }
+                            open_elements.pop();
+
+                            if config.repair_misnested {
+                                for element in unclosed.iter().rev() {
+                                    let end = BytesEnd::new(element.name.as_str());
+                                    output.insert_event(Event::End(end))?;
+                                }
+                                depth -= unclosed.len() as i32;
+                                let formatting =
+                                    unclosed.into_iter().filter(|el| el.start.is_some());
+                                reopen.extend(formatting.map(|el| OpenElement {
+                                    reopened: true,
+                                    ..el
+                                }));
+                            }
+                            verbatim = output.write_event(Event::End(e), source)?;
+                            depth -= 1;
+                        }
+                        None => {
+                            let message = format!("Unexpected end tag </{}>", tag_name);
+                            warnings.push(TransformWarning::new(html, source_start, message));
+
+                            if config.repair_misnested {
+                                // Browsers ignore the end tag, also when it closes an element
+                                // that is to be reopened
+                                if let Some(idx) = reopen.iter().rposition(|el| el.name == tag_name)
+                                {
+                                    reopen.remove(idx);
+                                }
+                            } else {
+                                verbatim = output.write_event(Event::End(e), source)?;
+                                depth -= 1;
+                            }
+                        }
+                    }
                 }
             }
 
             // Empty element (AKA void or self-closing tag, e.g. `<br />`)
             Ok(Event::Empty(e)) => {
+                check_duplicate_attributes(html, &e, source_start, &mut warnings);
+                let tag_name = config.element_name(e.name());
+                if config.csrf_token.is_some() && is_csrf_input(&tag_name, &e) {
+                    mark_csrf_found(&mut open_elements);
+                }
+                if depth == 0 {
+                    roots.push(tag_name);
+                }
                 let mut elem = e.into_owned();
-                add_attributes(config, &mut elem, depth == 0, &mut captured_attributes);
-                writer.write_event(Event::Empty(elem))?;
+                stats.elements += 1;
+                stats.attributes_added +=
+                    add_attributes(config, &mut elem, depth == 0, &mut captured_attributes);
+                verbatim = output.write_event(Event::Empty(elem), source)?;
             }
 
             // End of file
-            Ok(Event::Eof) => break,
+            Ok(Event::Eof) => {
+                // Browsers submit the content of unclosed forms too
+                insert_csrf_input(&mut output, &open_elements, &config.csrf_token)?;
+                if config.xml {
+                    // XML elements must be closed, there are no void elements
+                    if let Some(element) = open_elements.last() {
+                        let message = format!("Unclosed tag <{}>", element.name);
+                        return Err(Box::new(TransformError::new(html, element.offset, message)));
+                    }
+                } else if config.repair_misnested {
+                    // Close the elements that are still open
+                    for element in open_elements.iter().rev() {
+                        output.insert_event(Event::End(BytesEnd::new(element.name.as_str())))?;
+                    }
+                }
+                eof = true;
+            }
+            // Whitespace after a block-level tag, outside of e.g. `<pre>`
+            Ok(Event::Text(e))
+                if config.collapse_whitespace
+                    && after_block
+                    && e.iter().all(|b| b.is_ascii_whitespace())
+                    && !preserves_whitespace(&open_elements) =>
+            {
+                pending_whitespace = Some(source);
+            }
+            Ok(Event::Text(_))
+                if after_removed_comment && !preserves_whitespace(&open_elements) =>
+            {
+                let text = &html[source.clone()];
+                let trimmed = text.trim_start_matches(|c: char| c.is_ascii_whitespace());
+                let start = source.end - trimmed.len();
+                // Whitespace-only text is dropped whole, also if another removed comment follows
+                merge_whitespace = trimmed.is_empty();
+                if !trimmed.is_empty() {
+                    let text = BytesText::from_escaped(trimmed);
+                    verbatim = output.write_event(Event::Text(text), start..source.end)?;
+                }
+            }
+            // Comments that are stripped are not written
+            Ok(Event::Comment(e)) if config.strips_comment(&String::from_utf8_lossy(&e)) => {
+                merge_whitespace = html[..source.start]
+                    .ends_with(|c: char| c.is_ascii_whitespace())
+                    && !preserves_whitespace(&open_elements);
+            }
             // Other events (e.g. comments, processing instructions, etc.)
-            Ok(e) => writer.write_event(e)?,
-            Err(e) => return Err(Box::new(e)),
+            Ok(e) => verbatim = output.write_event(e, source)?,
+            Err(e) => {
+                tracing::debug!(
+                    position = reader.error_position(),
+                    error = %e,
+                    "Failed to parse HTML"
+                );
+                let offset = reader.error_position() as usize;
+                return Err(Box::new(TransformError::new(html, offset, e.to_string())));
+            }
+        }
+
+        if let Some(source_map) = source_map.as_mut() {
+            let source_end = reader.buffer_position() as usize;
+            // Not verbatim if e.g. end tags of unclosed elements were inserted before the event
+            let verbatim = verbatim && output.len() - output_start == source_end - source_start;
+            source_map.add(output_start, output.len(), source_start, source_end, verbatim);
+        }
+        after_block = block_boundary;
+        if eof {
+            break;
         }
     }
 
-    // Convert the transformed HTML to a string
-    let result = String::from_utf8(writer.into_inner().into_inner())?;
-    Ok((result, captured_attributes))
+    for element in open_elements.iter().filter(|el| config.warns_unclosed(el)) {
+        let message = format!("Unclosed tag <{}>", element.name);
+        warnings.push(TransformWarning::new(html, element.offset, message));
+    }
+    // Report in the order of appearance in the input
+    warnings.sort_by_key(|w| w.offset);
+
+    let result = output.finish();
+    tracing::debug!(
+        output_len = result.len(),
+        captured = captured_attributes.len(),
+        warnings = warnings.len(),
+        "Transformed HTML"
+    );
+    Ok(TransformResult {
+        html: result,
+        captured: captured_attributes,
+        source_map,
+        warnings,
+        roots,
+        stats,
+    })
 }
 
 #[cfg(test)]
@@ -167,7 +1115,7 @@ mod tests {
         );
 
         let input = "<div><p>Hello</p></div>";
-        let (result, _) = transform(&config, input).unwrap();
+        let result = transform(&config, input).unwrap().html;
 
         assert!(result.contains("data-root"));
         assert!(result.contains("data-all"));
@@ -183,7 +1131,7 @@ mod tests {
         );
 
         let input = "<div>First</div><span>Second</span>";
-        let (result, _) = transform(&config, input).unwrap();
+        let result = transform(&config, input).unwrap().html;
 
         // Both root elements should have data-root
         assert_eq!(result.matches("data-root").count(), 2);
@@ -222,7 +1170,7 @@ mod tests {
             </footer>
         "#;
 
-        let (result, _) = transform(&config, input).unwrap();
+        let result = transform(&config, input).unwrap().html;
 
         // Check root elements have root attributes
         assert!(result.contains(
@@ -273,7 +1221,7 @@ mod tests {
         ];
 
         for (input, expected) in test_cases {
-            let (result, _) = transform(&config, input).unwrap();
+            let result = transform(&config, input).unwrap().html;
             assert_eq!(result, expected);
         }
 
@@ -284,7 +1232,7 @@ mod tests {
             <p>Text with<br>break</p>
         </div>"#;
 
-        let (result, _) = transform(&config, input).unwrap();
+        let result = transform(&config, input).unwrap().html;
 
         // Verify void elements have attributes but no closing tags
         assert!(result.contains(r#"<link rel="stylesheet" href="style.css" data-v-123=""/>"#));
@@ -313,7 +1261,7 @@ mod tests {
                 <meta name="description" content="Test">
             </head>"#;
 
-        let (result, _) = transform(&config, input).unwrap();
+        let result = transform(&config, input).unwrap().html;
 
         // Check that it parsed successfully
         assert!(result.contains(r#"<meta charset="utf-8""#));
@@ -381,7 +1329,11 @@ mod tests {
                 <img data-id="789" src="test.jpg"/>
             </div>"#;
 
-        let (result, captured) = transform(&config, input).unwrap();
+        let TransformResult {
+            html: result,
+            captured,
+            ..
+        } = transform(&config, input).unwrap();
 
         println!("result: {}", result);
         println!("captured: {:?}", captured);
@@ -403,4 +1355,480 @@ mod tests {
             .iter()
             .any(|(id, attrs)| id == "789" && attrs.contains(&"data-v-123".to_string())));
     }
+
+    #[test]
+    fn test_source_map() {
+        let config = HtmlTransformerConfig::new(
+            vec!["data-root".to_string()],
+            vec!["data-v-123".to_string()],
+            false,
+            None,
+        )
+        .with_source_map(true);
+
+        let input = "<div><!-- hi --><br><p>Hello</p></br></div>";
+        let result = transform(&config, input).unwrap();
+        let source_map = result.source_map.unwrap();
+
+        // Each output range maps back to the input it was produced from
+        let pairs: Vec<(&str, &str)> = source_map
+            .mappings
+            .iter()
+            .map(|m| {
+                (
+                    &result.html[m.output_start..m.output_end],
+                    &input[m.source_start..m.source_end],
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            pairs,
+            vec![
+                (r#"<div data-root="" data-v-123="">"#, "<div>"),
+                ("<!-- hi -->", "<!-- hi -->"),
+                (r#"<br data-v-123=""/>"#, "<br>"),
+                (r#"<p data-v-123="">"#, "<p>"),
+                // Unchanged content is merged, void end tag `</br>` is dropped
+                ("Hello</p>", "Hello</p>"),
+                ("</div>", "</div>"),
+            ]
+        );
+        assert_eq!(
+            source_map.to_json(),
+            r#"{"version":1,"mappings":[[0,32,0,5],[32,43,5,16],[43,62,16,20],[62,79,20,23],[79,88,23,32],[88,94,37,43]]}"#
+        );
+
+        // Source map is not created unless enabled
+        let config = HtmlTransformerConfig::new(vec![], vec![], false, None);
+        assert!(transform(&config, input).unwrap().source_map.is_none());
+    }
+
+    #[test]
+    fn test_recovery_warnings() {
+        let config = HtmlTransformerConfig::new(vec![], vec!["data-v".to_string()], false, None);
+
+        let input = "<div>\n  <p class=\"a\" class=\"b\"><span>Hi</p>\n</div></i>\n<ul><li>";
+        let result = transform(&config, input).unwrap();
+        let warnings: Vec<String> = result.warnings.iter().map(|w| w.to_string()).collect();
+        assert_eq!(
+            warnings,
+            vec![
+                "Duplicate attribute 'class' in <p> at line 2, column 3",
+                "Unclosed tag <span> at line 2, column 26",
+                "Unexpected end tag </i> at line 3, column 7",
+                "Unclosed tag <ul> at line 4, column 1",
+            ]
+        );
+        assert_eq!(result.warnings[1].offset, input.find("<span>").unwrap());
+
+        // Well-formed HTML has no warnings, void elements don't need to be closed
+        let result = transform(&config, "<div><br><img src=\"x\"><p>Hi</p></div>").unwrap();
+        assert_eq!(result.warnings, vec![]);
+
+        // Nor do the elements with optional end tags
+        let input = "<ul><li>a<li>b</ul><p>x<table><tr><td>1<td>2</table><dl><dt>a<dd>b</dl>";
+        assert_eq!(transform(&config, input).unwrap().warnings, vec![]);
+    }
+
+    #[test]
+    fn test_roots_and_stats() {
+        let config = HtmlTransformerConfig::new(
+            vec!["data-root".to_string()],
+            vec!["data-v".to_string()],
+            false,
+            None,
+        );
+
+        let result = transform(&config, "<!-- c --><DIV><p>Hi<br></p></DIV>text<img/>").unwrap();
+        assert_eq!(result.roots, vec!["div", "img"]);
+        assert_eq!(
+            result.stats,
+            TransformStats {
+                elements: 4,
+                attributes_added: 6,
+            }
+        );
+    }
+
+    #[test]
+    fn test_attribute_order() {
+        let input = r#"<div id="x" Class='say "hi"' hidden><input disabled type=text></div>"#;
+        let config = |order| {
+            HtmlTransformerConfig::new(
+                vec!["data-root".to_string()],
+                vec!["data-a".to_string()],
+                false,
+                None,
+            )
+            .with_attribute_order(order)
+        };
+
+        let result = transform(&config(AttributeOrder::Alphabetical), input).unwrap();
+        assert_eq!(
+            result.html,
+            r#"<div Class="say &quot;hi&quot;" data-a="" data-root="" hidden="" id="x"><input data-a="" disabled="" type="text"/></div>"#
+        );
+
+        let priority = AttributeOrder::Priority(vec!["type".to_string(), "ID".to_string()]);
+        let result = transform(&config(priority), input).unwrap();
+        assert_eq!(
+            result.html,
+            r#"<div id="x" Class="say &quot;hi&quot;" hidden="" data-root="" data-a=""><input type="text" disabled="" data-a=""/></div>"#
+        );
+
+        // Attributes of untouched elements are left as they are
+        let config = HtmlTransformerConfig::new(vec![], vec![], false, None)
+            .with_attribute_order(AttributeOrder::Alphabetical);
+        assert_eq!(transform(&config, input).unwrap().html, input);
+
+        // Also when other elements get attributes, e.g. the nested elements with root attributes
+        let config = HtmlTransformerConfig::new(vec!["data-root".to_string()], vec![], false, None)
+            .with_attribute_order(AttributeOrder::Alphabetical);
+        assert_eq!(
+            transform(&config, "<div b a><p d c></p></div>")
+                .unwrap()
+                .html,
+            r#"<div a="" b="" data-root=""><p d c></p></div>"#
+        );
+    }
+
+    #[test]
+    fn test_repair_misnested() {
+        let config = HtmlTransformerConfig::new(vec![], vec!["data-v".to_string()], false, None)
+            .with_repair_misnested(true);
+        let repair = |input| transform(&config, input).unwrap();
+
+        // Formatting elements are reopened before the next content
+        assert_eq!(
+            repair("<b><i>1</b>2</i>").html,
+            r#"<b data-v=""><i data-v="">1</i></b><i data-v="">2</i>"#
+        );
+        assert_eq!(
+            repair("<b><i>1</b></i>2").html,
+            r#"<b data-v=""><i data-v="">1</i></b>2"#
+        );
+        // Other elements are only closed, and unexpected end tags removed
+        assert_eq!(
+            repair("<div><p>1</div>2</p></span>").html,
+            r#"<div data-v=""><p data-v="">1</p></div>2"#
+        );
+        // Unclosed elements are closed at the end
+        assert_eq!(
+            repair("<div><span>1").html,
+            r#"<div data-v=""><span data-v="">1</span></div>"#
+        );
+
+        // `</i>` closes the reopened element, so it's not unexpected as without the repair
+        let result = transform(&config, "<b><i>1</b>2</i><span>3").unwrap();
+        let warnings: Vec<String> = result.warnings.iter().map(|w| w.to_string()).collect();
+        assert_eq!(
+            warnings,
+            vec![
+                "Unclosed tag <i> at line 1, column 4",
+                "Unclosed tag <span> at line 1, column 17",
+            ]
+        );
+
+        // Inserted tags are mapped to the end tag that caused them
+        let config = config.with_source_map(true);
+        let result = transform(&config, "<b><i>1</b>2").unwrap();
+        assert_eq!(
+            result.html,
+            r#"<b data-v=""><i data-v="">1</i></b><i data-v="">2</i>"#
+        );
+        let mapped: Vec<_> = result
+            .source_map
+            .unwrap()
+            .mappings
+            .iter()
+            .map(|m| {
+                (
+                    &result.html[m.output_start..m.output_end],
+                    &"<b><i>1</b>2"[m.source_start..m.source_end],
+                )
+            })
+            .collect();
+        assert_eq!(
+            mapped,
+            vec![
+                (r#"<b data-v="">"#, "<b>"),
+                (r#"<i data-v="">"#, "<i>"),
+                ("1", "1"),
+                ("</i></b>", "</b>"),
+                (r#"<i data-v="">2"#, "2"),
+                ("</i>", ""),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_xml() {
+        let config = HtmlTransformerConfig::new(
+            vec!["data-root".to_string()],
+            vec!["data-v".to_string()],
+            false,
+            None,
+        )
+        .with_xml(true);
+
+        let input = concat!(
+            "<?xml version=\"1.0\"?>\n",
+            "<svg viewBox=\"0 0 10 10\"><linearGradient id=\"g\"/>",
+            "<style><![CDATA[ a > b { fill: red } ]]></style>",
+            "<link>https://example.com</link></svg>",
+        );
+        let result = transform(&config, input).unwrap();
+        assert_eq!(
+            result.html,
+            concat!(
+                "<?xml version=\"1.0\"?>\n",
+                "<svg viewBox=\"0 0 10 10\" data-root=\"\" data-v=\"\"><linearGradient id=\"g\" data-v=\"\"/>",
+                "<style data-v=\"\"><![CDATA[ a > b { fill: red } ]]></style>",
+                "<link data-v=\"\">https://example.com</link></svg>",
+            )
+        );
+        assert!(result.warnings.is_empty());
+
+        // Names are case-sensitive
+        let result = transform(&config, "<Item><item/></Item>").unwrap();
+        assert_eq!(result.roots, vec!["Item"]);
+        let err = transform(&config, "<Item></item>").unwrap_err();
+        assert!(err.to_string().contains("expected `</Item>`"), "{err}");
+
+        // Empty elements must be self-closing
+        let err = transform(&config, "<svg><br></svg>").unwrap_err();
+        assert!(err.to_string().contains("expected `</br>`"), "{err}");
+        let err = transform(&config, "<svg><g>").unwrap_err();
+        let err = err.downcast::<TransformError>().unwrap();
+        assert_eq!(err.message, "Unclosed tag <g>");
+        assert_eq!(err.column, 6);
+    }
+
+    #[test]
+    fn test_csrf_token() {
+        let config = HtmlTransformerConfig::new(vec![], vec![], false, None)
+            .with_csrf_token(Some("a\"b".to_string()));
+        let csrf = |input| transform(&config, input).unwrap().html;
+        let input = r#"<input type="hidden" name="csrfmiddlewaretoken" value="a&quot;b"/>"#;
+
+        assert_eq!(
+            csrf(r#"<form method="POST"><button>Send</button></form>"#),
+            format!(r#"<form method="POST"><button>Send</button>{input}</form>"#)
+        );
+        // Forms that already have the token, and GET forms, are left as they are
+        let has_token =
+            r#"<form method="post"><input name="csrfmiddlewaretoken" value="x"></form>"#;
+        assert_eq!(
+            csrf(has_token),
+            r#"<form method="post"><input name="csrfmiddlewaretoken" value="x"/></form>"#
+        );
+        assert_eq!(csrf("<form><input></form>"), "<form><input/></form>");
+        // Forms closed by the end tag of the parent, or at the end of the HTML, get it too
+        assert_eq!(
+            csrf("<div><form method=post></div><form method=post>"),
+            format!("<div><form method=post>{input}</div><form method=post>{input}")
+        );
+    }
+
+    #[test]
+    fn test_htmx() {
+        let attributes = vec![
+            ("hx-target".to_string(), "this".to_string()),
+            (
+                "hx-headers".to_string(),
+                r#"{"X-CSRFToken": "abc"}"#.to_string(),
+            ),
+        ];
+        let htmx = HtmxAttributes::new(attributes.clone(), &[]).unwrap();
+        let config = HtmlTransformerConfig::new(vec![], vec!["data-v".to_string()], false, None)
+            .with_htmx(Some(htmx));
+
+        // Root elements only, and not the attributes that the element already has
+        let result = transform(
+            &config,
+            r##"<div><p></p></div><form hx-target="#x"></form>"##,
+        )
+        .unwrap();
+        assert_eq!(
+            result.html,
+            concat!(
+                r#"<div data-v="" hx-target="this" hx-headers="{&quot;X-CSRFToken&quot;: &quot;abc&quot;}">"#,
+                r#"<p data-v=""></p></div>"#,
+                r##"<form hx-target="#x" data-v="" hx-headers="{&quot;X-CSRFToken&quot;: &quot;abc&quot;}"></form>"##,
+            )
+        );
+        assert_eq!(result.stats.attributes_added, 6);
+
+        // Elements that match the selectors, instead of root elements
+        let htmx = HtmxAttributes::new(attributes[..1].to_vec(), &["button.save".to_string()]);
+        let config = config.with_htmx(Some(htmx.unwrap()));
+        let result = transform(&config, r#"<div><button class="save"/><button/></div>"#).unwrap();
+        assert_eq!(
+            result.html,
+            r#"<div data-v=""><button class="save" data-v="" hx-target="this"/><button data-v=""/></div>"#
+        );
+    }
+
+    #[test]
+    fn test_directives() {
+        let config = HtmlTransformerConfig::new(vec![], vec!["data-v".to_string()], false, None);
+        let input = concat!(
+            r#"<div x-data="{ open: false }" @click="open = !open" :class="{ 'a': b > c }" "#,
+            r#"x-on:click.prevent hx-on::after-request='done("x")' v-bind:Foo=x #default>"#,
+            "</div>",
+        );
+
+        // Existing attributes are copied as they are
+        let result = transform(&config, input).unwrap();
+        assert_eq!(
+            result.html,
+            input.replace("#default>", "#default data-v=\"\">")
+        );
+
+        // Rewritten in the attribute order, unless preserved
+        let config = config.with_attribute_order(AttributeOrder::Alphabetical);
+        let result = transform(&config, input).unwrap();
+        assert_eq!(
+            result.html,
+            concat!(
+                r#"<div #default="" :class="{ 'a': b > c }" @click="open = !open" data-v="" "#,
+                r#"hx-on::after-request="done(&quot;x&quot;)" v-bind:Foo="x" x-data="{ open: false }" "#,
+                r#"x-on:click.prevent=""></div>"#,
+            )
+        );
+        let config = config.with_preserve_directives(true);
+        let result = transform(&config, input).unwrap();
+        assert_eq!(
+            result.html,
+            concat!(
+                r#"<div #default :class="{ 'a': b > c }" @click="open = !open" data-v="" "#,
+                r#"hx-on::after-request='done("x")' v-bind:Foo=x x-data="{ open: false }" "#,
+                r#"x-on:click.prevent></div>"#,
+            )
+        );
+    }
+
+    #[test]
+    fn test_strip_comments() {
+        let config =
+            HtmlTransformerConfig::new(vec![], vec![], false, None).with_strip_comments(true);
+        let input = concat!(
+            "<!-- TODO --><p>Hi<!--x--></p>",
+            "<!--[if IE]><p>IE</p><![endif]--><!--[if !IE]><!--><p>Not IE</p><!--<![endif]-->",
+            "<!-- _RENDERED table_10bac31,a1b2c3 -->",
+        );
+        let result = transform(&config, input).unwrap();
+        assert_eq!(
+            result.html,
+            concat!(
+                "<p>Hi</p>",
+                "<!--[if IE]><p>IE</p><![endif]--><!--[if !IE]><!--><p>Not IE</p><!--<![endif]-->",
+                "<!-- _RENDERED table_10bac31,a1b2c3 -->",
+            )
+        );
+
+        // Whitespace on both sides of a removed comment is merged, except where it's significant
+        let result = transform(
+            &config,
+            "<div> <!-- a --> <!-- b -->\n <p>Hi <!--c-->there</p></div>",
+        );
+        assert_eq!(result.unwrap().html, "<div> <p>Hi there</p></div>");
+        let result = transform(&config, "<pre>a <!-- c --> b</pre>").unwrap();
+        assert_eq!(result.html, "<pre>a  b</pre>");
+
+        let config = config.with_kept_comments(vec!["keep*".to_string()]);
+        let result = transform(&config, "<!-- keep me --><!--[if IE]>x<![endif]-->").unwrap();
+        assert_eq!(result.html, "<!-- keep me -->");
+
+        assert!(glob_match("a*c*e", "abcde"));
+        assert!(glob_match("*", ""));
+        assert!(glob_match("ab", "ab"));
+        assert!(!glob_match("ab", "abc"));
+        assert!(!glob_match("ab*ba", "aba"));
+    }
+
+    #[test]
+    fn test_collapse_whitespace() {
+        let config = HtmlTransformerConfig::new(vec![], vec![], false, None)
+            .with_collapse_whitespace(true)
+            .with_source_map(true);
+        let input = concat!(
+            "\n  <ul>\n    <li><b>a</b> <i>b</i></li>   <li>c </li>\n  </ul>\n",
+            "<pre>\n  <div>x</div>\n</pre>\n\n",
+        );
+        let result = transform(&config, input).unwrap();
+        assert_eq!(
+            result.html,
+            "\n<ul>\n<li><b>a</b> <i>b</i></li> <li>c </li>\n</ul>\n<pre>\n  <div>x</div>\n</pre>\n"
+        );
+
+        // The collapsed whitespace is mapped to the original whitespace
+        let mapping = result
+            .source_map
+            .unwrap()
+            .mappings
+            .into_iter()
+            .find(|m| m.source_start == 7)
+            .unwrap();
+        assert_eq!(&input[mapping.source_start..mapping.source_end], "\n    ");
+        assert_eq!(mapping.output_end - mapping.output_start, 1);
+
+        // Whitespace followed by text or an inline element is kept
+        let result = transform(&config, "<div>  <span>a</span>  </div><p>  b</p>").unwrap();
+        assert_eq!(result.html, "<div>  <span>a</span>  </div><p>  b</p>");
+    }
+
+    #[test]
+    fn test_check_only() {
+        let config = HtmlTransformerConfig::new(vec![], vec![], false, None).with_source_map(true);
+        let input = "<div >\n  <br><p class=a>Hi</br></p >\n</div><img>";
+        let result = transform(&config, input).unwrap();
+
+        // Tags are not rewritten, but the rest of the result is the same as with attributes
+        assert_eq!(result.html, "<div >\n  <br><p class=a>Hi</p >\n</div><img>");
+        assert_eq!(result.roots, vec!["div", "img"]);
+        assert_eq!(result.stats.elements, 4);
+        assert_eq!(result.stats.attributes_added, 0);
+        let mappings = result.source_map.unwrap().mappings;
+        assert_eq!(mappings.len(), 2);
+        assert_eq!(mappings[1].source_start, input.find("</p >").unwrap());
+
+        // Malformed HTML is still reported
+        let config = HtmlTransformerConfig::new(vec![], vec![], true, None);
+        assert!(transform(&config, "<div></span>").is_err());
+    }
+
+    #[test]
+    fn test_heap_size() {
+        let config = HtmlTransformerConfig::new(vec![], vec!["data-v".to_string()], false, None);
+        let small = transform(&config, "<p>Hi</p>").unwrap();
+        let large = transform(&config, &"<p>Hi</p>".repeat(100)).unwrap();
+
+        assert!(small.heap_size() >= small.html.len() + "p".len());
+        assert!(large.heap_size() > small.heap_size() + 99 * small.html.len());
+    }
+
+    #[test]
+    fn test_transform_error() {
+        let config = HtmlTransformerConfig::new(vec![], vec![], true, None);
+
+        let input = "<div>\n  <p>Ahoj světe</span>\n</div>";
+        let error = transform(&config, input).unwrap_err();
+        let error = error.downcast_ref::<TransformError>().unwrap();
+        assert_eq!(error.offset, input.find("</span>").unwrap());
+        assert_eq!((error.line, error.column), (2, 16));
+        assert_eq!(error.snippet, "  <p>Ahoj světe</span>");
+        assert!(error.to_string().ends_with(" at line 2, column 16"));
+
+        // Long lines are shortened around the problem
+        let input = format!("<div>{}</span>{}</div>", "a".repeat(100), "b".repeat(100));
+        let error = transform(&config, &input).unwrap_err();
+        let error = error.downcast_ref::<TransformError>().unwrap();
+        assert_eq!(error.column, 106);
+        assert_eq!(
+            error.snippet,
+            format!("{}</span>{}", "a".repeat(40), "b".repeat(33))
+        );
+    }
 }
@@ -0,0 +1,466 @@
+use crate::lexer::{string_value, JsError, Lexer, Token, TokenKind};
+
+/// How the dependency was declared
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DependencyKind {
+    /// `import x from "spec"`, `import { a } from "spec"`, `import * as ns from "spec"`
+    Import,
+    /// `import "spec"`
+    SideEffect,
+    /// `import("spec")`
+    Dynamic,
+    /// `export { a } from "spec"`, `export * from "spec"`
+    ReExport,
+}
+
+impl DependencyKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DependencyKind::Import => "import",
+            DependencyKind::SideEffect => "side_effect",
+            DependencyKind::Dynamic => "dynamic",
+            DependencyKind::ReExport => "reexport",
+        }
+    }
+}
+
+/// What the specifier points to. Only bare specifiers are resolved through an import map.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SpecifierKind {
+    /// E.g. `lodash` or `@scope/pkg/utils.js`
+    Bare,
+    /// E.g. `./utils.js` or `../utils.js`
+    Relative,
+    /// E.g. `/static/utils.js`
+    Absolute,
+    /// E.g. `https://cdn.example.com/utils.js` or `data:text/javascript,...`
+    Url,
+}
+
+impl SpecifierKind {
+    pub fn from_specifier(specifier: &str) -> Self {
+        if specifier.starts_with("./") || specifier.starts_with("../") {
+            SpecifierKind::Relative
+        } else if specifier.starts_with('/') {
+            SpecifierKind::Absolute
+        } else if has_url_scheme(specifier) {
+            SpecifierKind::Url
+        } else {
+            SpecifierKind::Bare
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SpecifierKind::Bare => "bare",
+            SpecifierKind::Relative => "relative",
+            SpecifierKind::Absolute => "absolute",
+            SpecifierKind::Url => "url",
+        }
+    }
+}
+
+// E.g. `https:`, `data:`, `blob:`. Scoped packages (`@scope/pkg`) never contain `:`.
+fn has_url_scheme(specifier: &str) -> bool {
+    match specifier.find(':') {
+        Some(idx) if idx > 0 => specifier[..idx]
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '-' || c == '.'),
+        _ => false,
+    }
+}
+
+/// A module that the JS depends on
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsDependency {
+    /// The module specifier, e.g. `./utils.js`
+    pub specifier: String,
+    pub kind: DependencyKind,
+    pub specifier_kind: SpecifierKind,
+    /// Names imported or re-exported from the module, as named in the module,
+    /// e.g. `["default", "a"]` for `import x, { a as b } from "spec"`. `*` stands for
+    /// a namespace import or a star re-export.
+    pub names: Vec<String>,
+    /// Byte offsets of the whole statement (or the `import(...)` call)
+    pub start: usize,
+    pub end: usize,
+    /// Byte offsets of the specifier string literal, including the quotes
+    pub specifier_start: usize,
+    pub specifier_end: usize,
+}
+
+/// Reads the tokens and recognizes import and export statements among them
+struct ImportScanner<'a> {
+    js: &'a str,
+    lexer: Lexer<'a>,
+    // Tokens read ahead of the current position
+    peeked: Option<Token>,
+    // The token before the current one
+    prev: Option<Token>,
+    dependencies: Vec<JsDependency>,
+}
+
+impl<'a> ImportScanner<'a> {
+    fn next(&mut self) -> Result<Option<Token>, JsError> {
+        let token = match self.peeked.take() {
+            Some(token) => Some(token),
+            None => self.lexer.next_token()?,
+        };
+        if token.is_some() {
+            self.prev = token;
+        }
+        Ok(token)
+    }
+
+    fn peek(&mut self) -> Result<Option<Token>, JsError> {
+        if self.peeked.is_none() {
+            self.peeked = self.lexer.next_token()?;
+        }
+        Ok(self.peeked)
+    }
+
+    fn run(&mut self) -> Result<(), JsError> {
+        let mut before: Option<Token> = None;
+        while let Some(token) = self.next()? {
+            // Skip property access like `obj.import` or `obj.export`
+            let is_member = before.is_some_and(|t| t.is_punct(b'.'));
+            if !is_member && token.is_word(self.js, "import") {
+                self.scan_import(token)?;
+            } else if !is_member && token.is_word(self.js, "export") {
+                self.scan_export(token)?;
+            }
+            before = self.prev;
+        }
+        Ok(())
+    }
+
+    fn push(&mut self, kind: DependencyKind, names: Vec<String>, start: usize, spec: Token) {
+        let specifier = string_value(self.js, &spec);
+        // Include the trailing semicolon in the statement
+        let mut end = spec.end;
+        if kind != DependencyKind::Dynamic {
+            if let Ok(Some(next)) = self.peek() {
+                if next.is_punct(b';') {
+                    end = next.end;
+                }
+            }
+        }
+
+        self.dependencies.push(JsDependency {
+            specifier_kind: SpecifierKind::from_specifier(&specifier),
+            specifier,
+            kind,
+            names,
+            start,
+            end,
+            specifier_start: spec.start,
+            specifier_end: spec.end,
+        });
+    }
+
+    /// Called after the `import` keyword
+    fn scan_import(&mut self, keyword: Token) -> Result<(), JsError> {
+        let Some(token) = self.peek()? else {
+            return Ok(());
+        };
+
+        match token.kind {
+            // `import "spec"`
+            TokenKind::String => {
+                self.next()?;
+                self.push(DependencyKind::SideEffect, Vec::new(), keyword.start, token);
+            }
+            // `import("spec")`. Calls with non-literal specifiers can't be resolved statically.
+            TokenKind::Punct(b'(') => {
+                self.next()?;
+                let Some(spec) = self.next()? else {
+                    return Ok(());
+                };
+                if !matches!(spec.kind, TokenKind::String | TokenKind::Template) {
+                    return Ok(());
+                }
+                match self.peek()? {
+                    Some(close) if close.is_punct(b')') || close.is_punct(b',') => {
+                        self.push(DependencyKind::Dynamic, Vec::new(), keyword.start, spec);
+                        let end = self.find_call_end(close)?;
+                        self.dependencies.last_mut().unwrap().end = end;
+                    }
+                    _ => {}
+                }
+            }
+            // `import.meta`
+            TokenKind::Punct(b'.') => {}
+            // `import x from "spec"`, `import { a } from "spec"`, etc.
+            _ => {
+                if let Some((names, spec)) = self.scan_clause_and_from()? {
+                    self.push(DependencyKind::Import, names, keyword.start, spec);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Called after the `export` keyword
+    fn scan_export(&mut self, keyword: Token) -> Result<(), JsError> {
+        match self.peek()? {
+            // `export * from "spec"`, `export * as ns from "spec"`, `export { a } from "spec"`
+            Some(token) if token.is_punct(b'*') || token.is_punct(b'{') => {
+                if let Some((names, spec)) = self.scan_clause_and_from()? {
+                    self.push(DependencyKind::ReExport, names, keyword.start, spec);
+                }
+            }
+            // Local exports like `export const x = 1` or `export default x`
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Read the import/export clause up to and including `from "spec"`.
+    /// Returns `None` if the statement is not one with a module specifier (e.g. `export { a };`).
+    fn scan_clause_and_from(&mut self) -> Result<Option<(Vec<String>, Token)>, JsError> {
+        let mut names = Vec::new();
+        let mut in_braces = false;
+        // Whether the next word in braces is an imported name (as opposed to the local alias after `as`)
+        let mut expect_name = true;
+
+        while let Some(token) = self.next()? {
+            match token.kind {
+                TokenKind::Punct(b'{') if !in_braces => in_braces = true,
+                TokenKind::Punct(b'}') if in_braces => in_braces = false,
+                TokenKind::Punct(b',') => expect_name = true,
+                TokenKind::Punct(b'*') if !in_braces => names.push("*".to_string()),
+                // `import { "string name" as x } from "spec"`
+                TokenKind::String if in_braces => {
+                    if expect_name {
+                        names.push(string_value(self.js, &token));
+                        expect_name = false;
+                    }
+                }
+                TokenKind::Word => {
+                    let text = token.text(self.js);
+                    if !in_braces && text == "from" {
+                        return match self.next()? {
+                            Some(spec) if spec.kind == TokenKind::String => Ok(Some((names, spec))),
+                            _ => Ok(None),
+                        };
+                    }
+                    if text == "as" {
+                        expect_name = false;
+                    } else if in_braces {
+                        // TypeScript's `import { type A } from "spec"`
+                        let is_type_modifier = text == "type"
+                            && self.peek()?.is_some_and(|next| next.kind == TokenKind::Word);
+                        if expect_name && !is_type_modifier {
+                            names.push(text.to_string());
+                            expect_name = false;
+                        }
+                    } else if expect_name && text != "type" {
+                        // Default import, `import x from "spec"`
+                        names.push("default".to_string());
+                        expect_name = false;
+                    }
+                }
+                // Anything else means this is not a statement we're interested in
+                _ => return Ok(None),
+            }
+        }
+        Ok(None)
+    }
+
+    /// Find the closing parenthesis of the `import(...)` call, skipping import attributes
+    /// like `import("./data.json", { with: { type: "json" } })`.
+    fn find_call_end(&mut self, after_specifier: Token) -> Result<usize, JsError> {
+        let mut depth = 0;
+        let mut end = after_specifier.end;
+        while let Some(token) = self.next()? {
+            end = token.end;
+            match token.kind {
+                TokenKind::Punct(b'(') | TokenKind::Punct(b'{') | TokenKind::Punct(b'[') => {
+                    depth += 1
+                }
+                TokenKind::Punct(b')') if depth == 0 => break,
+                TokenKind::Punct(b')') | TokenKind::Punct(b'}') | TokenKind::Punct(b']') => {
+                    depth -= 1
+                }
+                _ => {}
+            }
+        }
+        Ok(end)
+    }
+}
+
+/// Main entrypoint. Find all modules that the JS imports or re-exports from.
+pub fn extract(js: &str) -> Result<Vec<JsDependency>, JsError> {
+    let _span = tracing::debug_span!("extract_js_dependencies", js_len = js.len()).entered();
+    let mut scanner = ImportScanner {
+        js,
+        lexer: Lexer::new(js),
+        peeked: None,
+        prev: None,
+        dependencies: Vec::new(),
+    };
+    scanner
+        .run()
+        .inspect_err(|e| tracing::debug!(error = %e, "Failed to tokenize JS"))?;
+    tracing::debug!(
+        dependencies = scanner.dependencies.len(),
+        "Extracted JS dependencies"
+    );
+    Ok(scanner.dependencies)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(js: &str) -> Vec<(String, DependencyKind, Vec<String>)> {
+        extract(js)
+            .unwrap()
+            .into_iter()
+            .map(|dep| (dep.specifier, dep.kind, dep.names))
+            .collect()
+    }
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_static_imports() {
+        let js = r#"
+            import x from "./x.js";
+            import { a, b as c } from '../ab.js';
+            import y, * as ns from "lib";
+            import z, { default as d, "str name" as e } from "@scope/pkg/z.js";
+            import "./side-effect.js";
+            import {
+                f,
+                g,
+            } from "multi-line"
+        "#;
+
+        assert_eq!(
+            summary(js),
+            vec![
+                ("./x.js".to_string(), DependencyKind::Import, names(&["default"])),
+                ("../ab.js".to_string(), DependencyKind::Import, names(&["a", "b"])),
+                ("lib".to_string(), DependencyKind::Import, names(&["default", "*"])),
+                (
+                    "@scope/pkg/z.js".to_string(),
+                    DependencyKind::Import,
+                    names(&["default", "default", "str name"])
+                ),
+                ("./side-effect.js".to_string(), DependencyKind::SideEffect, names(&[])),
+                ("multi-line".to_string(), DependencyKind::Import, names(&["f", "g"])),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reexports() {
+        let js = r#"
+            export * from "./all.js";
+            export * as ns from "./ns.js";
+            export { a, b as c } from "./ab.js";
+            export { local };
+            export const x = 1;
+            export default function () {}
+        "#;
+
+        assert_eq!(
+            summary(js),
+            vec![
+                ("./all.js".to_string(), DependencyKind::ReExport, names(&["*"])),
+                ("./ns.js".to_string(), DependencyKind::ReExport, names(&["*"])),
+                ("./ab.js".to_string(), DependencyKind::ReExport, names(&["a", "b"])),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dynamic_imports() {
+        let js = r#"
+            const a = await import("./a.js");
+            const b = import(`./b.js`, { with: { type: "json" } });
+            const c = import(`./${name}.js`);
+            const d = import(name);
+            console.log(import.meta.url);
+        "#;
+
+        let deps = extract(js).unwrap();
+        assert_eq!(deps.len(), 2);
+        assert_eq!(deps[0].specifier, "./a.js");
+        assert_eq!(deps[0].kind, DependencyKind::Dynamic);
+        assert_eq!(&js[deps[0].start..deps[0].end], r#"import("./a.js")"#);
+        assert_eq!(deps[1].specifier, "./b.js");
+        assert_eq!(
+            &js[deps[1].start..deps[1].end],
+            r#"import(`./b.js`, { with: { type: "json" } })"#
+        );
+    }
+
+    #[test]
+    fn test_ignores_imports_in_strings_comments_and_regexes() {
+        let js = r#"
+            // import "./comment.js";
+            /* import x from "./block.js"; */
+            const s = 'import "./string.js"';
+            const t = `import "./template.js" ${ "x" }`;
+            const r = /import "\.\/regex.js"/;
+            obj.import("./method.js");
+            import "./real.js";
+        "#;
+
+        assert_eq!(
+            summary(js),
+            vec![("./real.js".to_string(), DependencyKind::SideEffect, names(&[]))]
+        );
+    }
+
+    #[test]
+    fn test_division_is_not_regex() {
+        let test_cases = [
+            "let i = 1;\nlet a = i++ / 2;\nimport x from \"y\";\n",
+            "let a = a-- / b;\nimport x from \"y\";\n",
+            "x = {} / 2;\nimport x from \"y\";\n",
+        ];
+        for js in test_cases {
+            assert_eq!(
+                summary(js),
+                vec![("y".to_string(), DependencyKind::Import, names(&["default"]))],
+                "{}",
+                js
+            );
+        }
+    }
+
+    #[test]
+    fn test_positions() {
+        let js = r#"import { a } from "./a.js"; import "./b.js""#;
+        let deps = extract(js).unwrap();
+
+        assert_eq!(&js[deps[0].start..deps[0].end], r#"import { a } from "./a.js";"#);
+        assert_eq!(
+            &js[deps[0].specifier_start..deps[0].specifier_end],
+            r#""./a.js""#
+        );
+        assert_eq!(&js[deps[1].start..deps[1].end], r#"import "./b.js""#);
+    }
+
+    #[test]
+    fn test_specifier_kinds() {
+        let test_cases = [
+            ("lodash", SpecifierKind::Bare),
+            ("@scope/pkg", SpecifierKind::Bare),
+            ("./a.js", SpecifierKind::Relative),
+            ("../a.js", SpecifierKind::Relative),
+            ("/static/a.js", SpecifierKind::Absolute),
+            ("https://cdn.example.com/a.js", SpecifierKind::Url),
+            ("data:text/javascript,export default 1", SpecifierKind::Url),
+        ];
+
+        for (specifier, expected) in test_cases {
+            assert_eq!(SpecifierKind::from_specifier(specifier), expected);
+        }
+    }
+}
@@ -0,0 +1,459 @@
+use std::fmt;
+
+// Keywords after which a `/` starts a regex literal rather than a division,
+// e.g. `return /abc/.test(x)`.
+const REGEX_PRECEDING_KEYWORDS: [&str; 15] = [
+    "return",
+    "typeof",
+    "instanceof",
+    "in",
+    "of",
+    "new",
+    "delete",
+    "void",
+    "throw",
+    "case",
+    "do",
+    "else",
+    "yield",
+    "await",
+    "extends",
+];
+
+/// Error raised when the JS cannot be tokenized, e.g. because of an unclosed string or comment.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsError {
+    pub message: String,
+    /// Byte offset into the input where the error was detected.
+    pub offset: usize,
+}
+
+impl fmt::Display for JsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at position {}", self.message, self.offset)
+    }
+}
+
+impl std::error::Error for JsError {}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TokenKind {
+    /// Identifier, keyword, or number
+    Word,
+    /// String literal, `"..."` or `'...'`
+    String,
+    /// Template literal without substitutions, e.g. `` `abc` ``
+    Template,
+    /// Part of a template literal with substitutions, e.g. `` `abc${ `` or `` }abc` ``
+    TemplatePart,
+    Regex,
+    /// `++` or `--`
+    Update,
+    /// Any other single character, e.g. `(`, `{`, `*`, `,`
+    Punct(u8),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Token {
+    pub kind: TokenKind,
+    /// Byte offsets into the input
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Token {
+    pub fn text<'a>(&self, js: &'a str) -> &'a str {
+        &js[self.start..self.end]
+    }
+
+    pub fn is_word(&self, js: &str, word: &str) -> bool {
+        self.kind == TokenKind::Word && self.text(js) == word
+    }
+
+    pub fn is_punct(&self, c: u8) -> bool {
+        self.kind == TokenKind::Punct(c)
+    }
+}
+
+/// Minimal JS tokenizer. It only distinguishes what's needed to find import and export
+/// statements reliably, i.e. to not mistake text inside strings, comments, template
+/// literals or regexes for code.
+pub struct Lexer<'a> {
+    js: &'a str,
+    pos: usize,
+    // For each open `{`, whether it starts an expression (e.g. an object literal)
+    // rather than a block.
+    braces: Vec<bool>,
+    // For each template literal we're inside of (in a `${ }` substitution),
+    // the brace depth at which the template literal continues.
+    template_stack: Vec<usize>,
+    last: Option<Token>,
+    // Whether the last token ends an operand, e.g. `x`, `)` or `x++`,
+    // so that a following `/` is a division.
+    last_ends_operand: bool,
+    // Set on a `function` keyword in an expression, so that its body's `{` is
+    // treated as the start of an expression.
+    function_expression: bool,
+}
+
+impl<'a> Lexer<'a> {
+    pub fn new(js: &'a str) -> Self {
+        Lexer {
+            js,
+            pos: 0,
+            braces: Vec::new(),
+            template_stack: Vec::new(),
+            last: None,
+            last_ends_operand: false,
+            function_expression: false,
+        }
+    }
+
+    fn error(&self, message: &str, offset: usize) -> JsError {
+        JsError {
+            message: message.to_string(),
+            offset,
+        }
+    }
+
+    /// Whether the previous token starts a new statement, so that a `{` opens a block
+    fn at_statement_start(&self) -> bool {
+        match self.last {
+            None => true,
+            Some(token) => match token.kind {
+                TokenKind::Word => matches!(token.text(self.js), "else" | "do"),
+                // Labels and `case x:` are followed by blocks, object properties by values
+                TokenKind::Punct(b':') => self.braces.last() == Some(&false),
+                // Arrow function body, `=>`
+                TokenKind::Punct(b'>') => self.js[..token.start].ends_with('='),
+                TokenKind::Punct(c) => matches!(c, b';' | b'{' | b'}'),
+                _ => false,
+            },
+        }
+    }
+
+    /// Whether the current position starts an expression, e.g. after `=` or `(`
+    fn at_expression_start(&self) -> bool {
+        !self.last_ends_operand && !self.at_statement_start()
+    }
+
+    /// Whether the given token ends an operand, i.e. a `/` after it is a division
+    fn ends_operand(&self, token: &Token, closed_expression: bool) -> bool {
+        match token.kind {
+            TokenKind::Word => !REGEX_PRECEDING_KEYWORDS.contains(&token.text(self.js)),
+            TokenKind::Punct(b'}') => closed_expression,
+            TokenKind::Punct(c) => matches!(c, b')' | b']'),
+            TokenKind::TemplatePart => !token.text(self.js).ends_with("${"),
+            // Postfix `x++` ends an operand, prefix `++x` does not
+            TokenKind::Update => self.last_ends_operand,
+            TokenKind::String | TokenKind::Template | TokenKind::Regex => true,
+        }
+    }
+
+    fn skip_trivia(&mut self) -> Result<(), JsError> {
+        let bytes = self.js.as_bytes();
+        while self.pos < bytes.len() {
+            match bytes[self.pos] {
+                b if b.is_ascii_whitespace() => self.pos += 1,
+                b'/' if bytes.get(self.pos + 1) == Some(&b'/') => {
+                    self.pos = self.js[self.pos..]
+                        .find('\n')
+                        .map_or(bytes.len(), |idx| self.pos + idx);
+                }
+                b'/' if bytes.get(self.pos + 1) == Some(&b'*') => {
+                    match self.js[self.pos + 2..].find("*/") {
+                        Some(idx) => self.pos += 2 + idx + 2,
+                        None => return Err(self.error("Unclosed comment", self.pos)),
+                    }
+                }
+                // Non-ASCII whitespace, e.g. non-breaking space or BOM
+                b if b >= 0x80 => {
+                    let c = self.js[self.pos..].chars().next().unwrap();
+                    if !(c.is_whitespace() || c == '\u{feff}') {
+                        return Ok(());
+                    }
+                    self.pos += c.len_utf8();
+                }
+                _ => return Ok(()),
+            }
+        }
+        Ok(())
+    }
+
+    /// Return the end of the string starting at `start` (which points at the opening quote)
+    fn string_end(&self, start: usize) -> Result<usize, JsError> {
+        let bytes = self.js.as_bytes();
+        let quote = bytes[start];
+        let mut i = start + 1;
+        while i < bytes.len() {
+            match bytes[i] {
+                b'\\' => i += 2,
+                b'\n' => break,
+                b if b == quote => return Ok(i + 1),
+                _ => i += 1,
+            }
+        }
+        Err(self.error("Unclosed string", start))
+    }
+
+    /// Scan the template literal from `start` (which points right after `` ` `` or `}`)
+    /// until its end or the next `${`. Returns the end and whether the template literal ended.
+    fn template_end(&self, start: usize) -> Result<(usize, bool), JsError> {
+        let bytes = self.js.as_bytes();
+        let mut i = start;
+        while i < bytes.len() {
+            match bytes[i] {
+                b'\\' => i += 2,
+                b'`' => return Ok((i + 1, true)),
+                b'$' if bytes.get(i + 1) == Some(&b'{') => return Ok((i + 2, false)),
+                _ => i += 1,
+            }
+        }
+        Err(self.error("Unclosed template literal", start))
+    }
+
+    fn regex_end(&self, start: usize) -> Result<usize, JsError> {
+        let bytes = self.js.as_bytes();
+        let mut i = start + 1;
+        let mut in_class = false;
+        while i < bytes.len() {
+            match bytes[i] {
+                b'\\' => i += 2,
+                b'\n' => break,
+                b'[' => {
+                    in_class = true;
+                    i += 1;
+                }
+                b']' => {
+                    in_class = false;
+                    i += 1;
+                }
+                b'/' if !in_class => {
+                    // Flags, e.g. `/abc/gi`
+                    i += 1;
+                    while i < bytes.len() && bytes[i].is_ascii_alphabetic() {
+                        i += 1;
+                    }
+                    return Ok(i);
+                }
+                _ => i += 1,
+            }
+        }
+        Err(self.error("Unclosed regular expression", start))
+    }
+
+    fn word_end(&self, start: usize) -> usize {
+        self.js[start..]
+            .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '$' || c == '\\'))
+            .map_or(self.js.len(), |idx| start + idx)
+    }
+
+    /// Read the next token. Returns `None` at the end of input.
+    pub fn next_token(&mut self) -> Result<Option<Token>, JsError> {
+        self.skip_trivia()?;
+        let start = self.pos;
+        let Some(&b) = self.js.as_bytes().get(start) else {
+            if !self.template_stack.is_empty() {
+                return Err(self.error("Unclosed template literal", start));
+            }
+            return Ok(None);
+        };
+
+        let mut closed_expression = false;
+        let (kind, end) = match b {
+            b'"' | b'\'' => (TokenKind::String, self.string_end(start)?),
+            b'`' => match self.template_end(start + 1)? {
+                (end, true) => (TokenKind::Template, end),
+                (end, false) => {
+                    self.template_stack.push(self.braces.len());
+                    (TokenKind::TemplatePart, end)
+                }
+            },
+            b'}' if self.template_stack.last() == Some(&self.braces.len()) => {
+                self.template_stack.pop();
+                match self.template_end(start + 1)? {
+                    (end, true) => (TokenKind::TemplatePart, end),
+                    (end, false) => {
+                        self.template_stack.push(self.braces.len());
+                        (TokenKind::TemplatePart, end)
+                    }
+                }
+            }
+            b'/' if !self.last_ends_operand => (TokenKind::Regex, self.regex_end(start)?),
+            b'+' | b'-' if self.js.as_bytes().get(start + 1) == Some(&b) => {
+                (TokenKind::Update, start + 2)
+            }
+            b'{' => {
+                let function_body = self.function_expression
+                    && self.last.is_some_and(|token| token.is_punct(b')'));
+                if function_body {
+                    self.function_expression = false;
+                }
+                self.braces.push(function_body || self.at_expression_start());
+                (TokenKind::Punct(b), start + 1)
+            }
+            b'}' => {
+                closed_expression = self.braces.pop().unwrap_or(false);
+                (TokenKind::Punct(b), start + 1)
+            }
+            b if b.is_ascii_punctuation() && b != b'_' && b != b'$' && b != b'\\' => {
+                (TokenKind::Punct(b), start + 1)
+            }
+            _ => {
+                // Numbers like `1.5` are read as a single word
+                let mut end = self.word_end(start);
+                if b.is_ascii_digit() && self.js.as_bytes().get(end) == Some(&b'.') {
+                    end = self.word_end(end + 1);
+                }
+                // Avoid infinite loop on characters that are neither words nor punctuation
+                let first_len = self.js[start..].chars().next().map_or(1, |c| c.len_utf8());
+                (TokenKind::Word, end.max(start + first_len))
+            }
+        };
+
+        self.pos = end;
+        let token = Token { kind, start, end };
+        if token.is_word(self.js, "function") && self.at_expression_start() {
+            self.function_expression = true;
+        }
+        self.last_ends_operand = self.ends_operand(&token, closed_expression);
+        self.last = Some(token);
+        Ok(Some(token))
+    }
+}
+
+/// Get the value of a string literal or a template literal without substitutions,
+/// i.e. the content between the quotes with escape sequences resolved.
+pub fn string_value(js: &str, token: &Token) -> String {
+    let inner = &js[token.start + 1..token.end - 1];
+    let mut value = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            if let Some(escaped) = chars.next() {
+                value.push(escaped);
+            }
+        } else {
+            value.push(c);
+        }
+    }
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokenize(js: &str) -> Vec<(TokenKind, &str)> {
+        let mut lexer = Lexer::new(js);
+        let mut tokens = Vec::new();
+        while let Some(token) = lexer.next_token().unwrap() {
+            tokens.push((token.kind, token.text(js)));
+        }
+        tokens
+    }
+
+    #[test]
+    fn test_strings_and_comments() {
+        let js = r#"a = "x // y"; // import "no"
+/* import "no" */ b = 'it\'s';"#;
+        assert_eq!(
+            tokenize(js),
+            vec![
+                (TokenKind::Word, "a"),
+                (TokenKind::Punct(b'='), "="),
+                (TokenKind::String, "\"x // y\""),
+                (TokenKind::Punct(b';'), ";"),
+                (TokenKind::Word, "b"),
+                (TokenKind::Punct(b'='), "="),
+                (TokenKind::String, "'it\\'s'"),
+                (TokenKind::Punct(b';'), ";"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_regex_vs_division() {
+        let js = "x = a / b / c; y = /ab[/]c/gi.test(z); return /'/;";
+        let kinds: Vec<_> = tokenize(js)
+            .into_iter()
+            .filter(|(kind, _)| *kind == TokenKind::Regex)
+            .map(|(_, text)| text)
+            .collect();
+        assert_eq!(kinds, vec!["/ab[/]c/gi", "/'/"]);
+    }
+
+    #[test]
+    fn test_division_after_operands() {
+        let test_cases = [
+            "a = i++ / 2; /x/",
+            "a = a-- / b; /x/",
+            "x = {} / 2; /x/",
+            "x = function() {} / 2; /x/",
+            "x = {a: {}} / 2; /x/",
+            "if (a) {} /x/",
+            "x = ++/x/.lastIndex",
+            "x = () => {} /x/",
+        ];
+        for js in test_cases {
+            let regexes: Vec<_> = tokenize(js)
+                .into_iter()
+                .filter(|(kind, _)| *kind == TokenKind::Regex)
+                .map(|(_, text)| text)
+                .collect();
+            assert_eq!(regexes, vec!["/x/"], "{}", js);
+        }
+        assert_eq!(
+            tokenize("i++ - --j"),
+            vec![
+                (TokenKind::Word, "i"),
+                (TokenKind::Update, "++"),
+                (TokenKind::Punct(b'-'), "-"),
+                (TokenKind::Update, "--"),
+                (TokenKind::Word, "j"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_template_literals() {
+        let js = "a = `x ${ {b: `y${c}`}.b } 'z`; d = `plain`;";
+        assert_eq!(
+            tokenize(js),
+            vec![
+                (TokenKind::Word, "a"),
+                (TokenKind::Punct(b'='), "="),
+                (TokenKind::TemplatePart, "`x ${"),
+                (TokenKind::Punct(b'{'), "{"),
+                (TokenKind::Word, "b"),
+                (TokenKind::Punct(b':'), ":"),
+                (TokenKind::TemplatePart, "`y${"),
+                (TokenKind::Word, "c"),
+                (TokenKind::TemplatePart, "}`"),
+                (TokenKind::Punct(b'}'), "}"),
+                (TokenKind::Punct(b'.'), "."),
+                (TokenKind::Word, "b"),
+                (TokenKind::TemplatePart, "} 'z`"),
+                (TokenKind::Punct(b';'), ";"),
+                (TokenKind::Word, "d"),
+                (TokenKind::Punct(b'='), "="),
+                (TokenKind::Template, "`plain`"),
+                (TokenKind::Punct(b';'), ";"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unclosed() {
+        for js in ["a = 'x", "/* x", "a = `x${y}"] {
+            let mut lexer = Lexer::new(js);
+            let result = loop {
+                match lexer.next_token() {
+                    Ok(Some(_)) => continue,
+                    other => break other,
+                }
+            };
+            assert!(result.is_err(), "{}", js);
+        }
+    }
+}
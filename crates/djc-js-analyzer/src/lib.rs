@@ -0,0 +1,26 @@
+use imports::extract;
+
+pub mod imports;
+pub mod lexer;
+
+// Re-export the types that users need
+pub use imports::{DependencyKind, JsDependency, SpecifierKind};
+pub use lexer::JsError;
+
+/// Find all modules that the JS imports or re-exports from.
+///
+/// Recognizes static imports (`import x from "spec"`), side-effect imports (`import "spec"`),
+/// dynamic imports with a literal specifier (`import("spec")`) and re-exports
+/// (`export { a } from "spec"`). Text inside strings, comments, template literals
+/// and regexes is ignored.
+///
+/// Args:
+///     js: The JavaScript source code.
+///
+/// Returns:
+///     A Result containing either:
+///     - Ok(dependencies): The dependencies in the order they appear in the source
+///     - Err(error): An error if the JS cannot be tokenized, e.g. has unclosed strings or comments.
+pub fn extract_js_dependencies(js: &str) -> Result<Vec<JsDependency>, Box<dyn std::error::Error>> {
+    Ok(extract(js)?)
+}
@@ -1 +1,6 @@
+from djc_core.djc_core_features import *
+from djc_core.djc_core_logging import *
+from djc_core.djc_core_stats import *
+from djc_core.djc_css_transformer import *
 from djc_core.djc_html_transformer import *
+from djc_core.djc_js_analyzer import *
@@ -0,0 +1,37 @@
+from typing import Dict, List, TypedDict
+
+class Features(TypedDict):
+    version: str
+    # Feature name -> whether it's enabled, e.g. `parallel`
+    features: Dict[str, bool]
+    functions: List[str]
+    # Keyword arguments of the HTML transformer functions, e.g. `xml`
+    options: List[str]
+
+def features() -> Features:
+    """
+    Get the version of the package and the optional features it was built with,
+    so callers can detect what's supported instead of pinning exact versions.
+
+    The keyword arguments of all the functions can be inspected with `inspect.signature()`.
+
+    Returns:
+        Dictionary with keys:
+            - `version` (str): Version of the package, e.g. `1.1.0`
+            - `features` (Dict[str, bool]): Whether each of the optional features is enabled:
+                - `cache`: The LRU cache of `set_html_attributes()` results, see `configure_cache()`
+                - `parallel`: Parallel batches with `set_html_attributes_many(parallel=True)`
+                - `wasm`: Whether it's the WebAssembly build, always `False` for Python
+            - `functions` (List[str]): Names of all functions of the module, in alphabetical order
+            - `options` (List[str]): Supported options of the HTML transformer, i.e. the keyword arguments
+              of `set_html_attributes()`, `set_html_attributes_many()` and `transform_html_file()`
+
+    Example:
+        >>> features()["features"]
+        {'cache': True, 'parallel': True, 'wasm': False}
+        >>> "collapse_whitespace" in features()["options"]
+        True
+    """
+    ...
+
+__all__ = ["Features", "features"]
@@ -0,0 +1,27 @@
+def enable_logging(enabled: bool = True, level: int = 10) -> None:
+    """
+    Forward the diagnostic events of the Rust code (e.g. where parsing failed, or how long
+    the output was) to the Python logger `djc_core`.
+
+    Events are forwarded as log records with the corresponding level. Rust's `TRACE` events
+    are logged with level 5, below `logging.DEBUG`. The message is prefixed with the name of
+    the operation, e.g. `transform_html: Failed to parse HTML position=12 error=...`.
+
+    Args:
+        enabled (bool): Whether to forward the events. Defaults to True.
+        level (int): Minimum level of the forwarded events. Defaults to `logging.DEBUG`.
+
+    Example:
+        >>> import logging
+        >>> logging.basicConfig(level=logging.DEBUG)
+        >>> enable_logging()
+        >>> html, captured = set_html_attributes('<div></div>', [], ['data-v-123'])
+        DEBUG:djc_core:transform_html: Transformed HTML output_len=25 captured=0 warnings=0
+
+    Raises:
+        RuntimeError: If another library already installed a global `tracing` subscriber. Raised by
+            every call that enables the logging, never when disabling it.
+    """
+    ...
+
+__all__ = ["enable_logging"]
@@ -0,0 +1,46 @@
+def scope_css(css: str, scope_attribute: str) -> str:
+    """
+    Scope CSS to a component by appending an attribute selector to all selectors.
+
+    Selectors inside `@media`, `@supports`, `@container` and `@layer` blocks are scoped too.
+    Keyframes, font faces and declarations are left untouched.
+
+    Args:
+        css (str): The CSS string to transform.
+        scope_attribute (str): Name of the attribute to scope the selectors with, e.g. `data-djc-c1a2`.
+
+    Returns:
+        The CSS with scoped selectors.
+
+    Example:
+        >>> scope_css('.btn > p:hover { color: red }', 'data-djc-c1a2')
+        '.btn > p[data-djc-c1a2]:hover { color: red }'
+
+    Raises:
+        ValueError: If the CSS is malformed, e.g. has unclosed blocks.
+    """
+    ...
+
+def minify_css(css: str) -> str:
+    """
+    Minify CSS by removing comments and insignificant whitespace, and by shortening numbers
+    (e.g. `0px` -> `0`, `0.5em` -> `.5em`).
+
+    Comments starting with `/*!` (e.g. licenses) are kept.
+
+    Args:
+        css (str): The CSS string to minify.
+
+    Returns:
+        The minified CSS.
+
+    Example:
+        >>> minify_css('.btn > p { margin: 0px 0.5em; }')
+        '.btn>p{margin:0 .5em}'
+
+    Raises:
+        ValueError: If the CSS is malformed, e.g. has unclosed strings or comments.
+    """
+    ...
+
+__all__ = ["scope_css", "minify_css"]
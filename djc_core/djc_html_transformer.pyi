@@ -1,12 +1,132 @@
-from typing import List, Dict, Optional
+import os
+from typing import Any, Dict, Iterator, List, Literal, Optional, Tuple, TypedDict, Union
+
+class CacheInfo(TypedDict):
+    hits: int
+    misses: int
+    # 0 if the cache is disabled
+    maxsize: int
+    currsize: int
+    # Approximate number of bytes taken by the cached results
+    heap_size: int
+
+class DjcHtmlRecoveryWarning(UserWarning):
+    """
+    Issued when the HTML is malformed, but could still be transformed, e.g. has unclosed tags.
+
+    Turn these into errors with `warnings.simplefilter("error", DjcHtmlRecoveryWarning)`
+    or pytest's `-W error::djc_core.DjcHtmlRecoveryWarning`.
+    """
+
+    # File the HTML was read from, if any
+    filename: Optional[str]
+    # 1-based position of the problem in the HTML
+    line: int
+    column: int
+
+class HtmlTransformError(ValueError):
+    """
+    Raised when the HTML is malformed and cannot be transformed.
+    """
+
+    # File the HTML was read from, if any
+    filename: Optional[str]
+    # 1-based position of the problem in the HTML
+    line: int
+    column: int
+    # Position of the problem in characters
+    offset: int
+    # The line with the problem, shortened to at most 80 characters around it
+    source_snippet: str
+
+class HtmlAsset(TypedDict):
+    kind: Literal["script", "link", "image", "inline_script", "inline_style"]
+    # None for inline assets
+    url: Optional[str]
+    rel: Optional[str]
+    # Only for inline assets
+    content: Optional[str]
+    hash: Optional[str]
+    start: int
+    end: int
+
+class HtmlTransformStats(TypedDict):
+    # Number of elements, excluding end tags
+    elements: int
+    # Number of attributes added to all elements together
+    attributes_added: int
+
+class SourceMap(TypedDict):
+    version: int
+    # Each mapping is [output_start, output_end, source_start, source_end]
+    mappings: List[List[int]]
+
+class HtmlTransformResult:
+    """
+    Result of `set_html_attributes()`, `set_html_attributes_many()` and `transform_html_file()`.
+
+    For backwards compatibility, the result also behaves like the `(html, captured)` tuple,
+    or `(html, captured, source_map)` if the source map was requested.
+    """
+
+    @property
+    def html(self) -> str:
+        """The transformed HTML."""
+        ...
+    @property
+    def captured(self) -> Dict[str, List[str]]:
+        """
+        Captured attribute values mapped to the attributes that were added to those elements.
+        Empty if `watch_on_attribute` wasn't set.
+        """
+        ...
+    def iter_captured(self) -> Iterator[Tuple[str, List[str]]]:
+        """
+        Iterate over the `(value, attributes)` pairs of the captured elements, in the order
+        of appearance, including elements with the same value. Unlike `captured`, this doesn't
+        create all the Python objects at once.
+        """
+        ...
+    @property
+    def source_map(self) -> Optional[SourceMap]:
+        """Mapping of the output back to the input, `None` unless requested with `source_map=True`."""
+        ...
+    @property
+    def roots(self) -> List[str]:
+        """
+        Tag names of the root elements, in the order of appearance. Lowercased, except with `xml=True`,
+        where the names are case-sensitive and kept as they are.
+        """
+        ...
+    @property
+    def stats(self) -> HtmlTransformStats: ...
+    @property
+    def warnings(self) -> List[DjcHtmlRecoveryWarning]:
+        """Warnings issued for the problems that were worked around. Empty for cached results."""
+        ...
+    def __len__(self) -> int: ...
+    def __getitem__(self, index: int) -> Any: ...
+    def __iter__(self) -> Iterator[Any]: ...
 
 def set_html_attributes(
     html: str,
     root_attributes: List[str],
     all_attributes: List[str],
+    *,
     check_end_names: Optional[bool] = None,
     watch_on_attribute: Optional[str] = None,
-) -> tuple[str, Dict[str, List[str]]]:
+    source_map: Optional[bool] = None,
+    attribute_order: Optional[Union[Literal["original", "alphabetical"], List[str]]] = None,
+    repair_misnested: Optional[bool] = None,
+    xml: Optional[bool] = None,
+    csrf_token: Optional[str] = None,
+    htmx_attributes: Optional[Dict[str, Any]] = None,
+    htmx_selectors: Optional[List[str]] = None,
+    preserve_directives: Optional[bool] = None,
+    strip_comments: Optional[bool] = None,
+    keep_comments: Optional[List[str]] = None,
+    collapse_whitespace: Optional[bool] = None,
+) -> HtmlTransformResult:
     """
     Transform HTML by adding attributes to root and all elements.
 
@@ -16,21 +136,303 @@ def set_html_attributes(
         all_attributes (List[str]): List of attribute names to add to all elements.
         check_end_names (Optional[bool]): Whether to validate matching of end tags. Defaults to None.
         watch_on_attribute (Optional[str]): If set, captures which attributes were added to elements with this attribute.
+        source_map (Optional[bool]): If set, also returns a mapping of the output back to the input. Defaults to None.
+        attribute_order (Optional[Union[str, List[str]]]): Order of the attributes of the elements that attributes
+            are added to. `"original"` (default) keeps the existing attributes first, `"alphabetical"` sorts them
+            by name, and a list of attribute names puts those first, followed by the rest in the original order.
+        repair_misnested (Optional[bool]): Whether to repair misnested and unclosed tags the way browsers do,
+            instead of keeping them as they are. Ignored with `check_end_names`. Defaults to None.
+        xml (Optional[bool]): Whether to parse the input as XML, e.g. SVG, instead of HTML. Tag names
+            are case-sensitive, there are no void elements, and misnested or unclosed tags raise an error.
+            Defaults to None.
+        csrf_token (Optional[str]): If set, a hidden `csrfmiddlewaretoken` input with this value is inserted
+            into every `<form method="post">` that doesn't have it yet. Defaults to None.
+        htmx_attributes (Optional[Dict[str, Any]]): `hx-*` attributes to add to the root elements, e.g.
+            `{"hx-target": "this"}`. Values that are not strings, e.g. a dict for `hx-headers`, are serialized
+            as JSON. Attributes that the element already has are not added. Defaults to None.
+        htmx_selectors (Optional[List[str]]): Add the `htmx_attributes` to the elements that match any of
+            these selectors instead of the root elements. Only tag names, `#id`, `.class` and `[attribute]`
+            are supported, e.g. `"button.save"`. Defaults to None.
+        preserve_directives (Optional[bool]): Whether to keep the attributes of frontend frameworks, e.g. `@click`,
+            `:class` or `x-on:click.prevent`, exactly as they are written when the attributes are rewritten
+            in the `attribute_order`. Defaults to None.
+        strip_comments (Optional[bool]): Whether to remove HTML comments, except those that match `keep_comments`.
+            Defaults to None.
+        keep_comments (Optional[List[str]]): Patterns of the comments to keep when stripping comments, matched
+            against the content of the comment without the surrounding whitespace. `*` matches any text.
+            Defaults to conditional comments and django-components markers:
+            `["[if *", "<![endif]*", "_RENDERED *", "djc-*"]`.
+        collapse_whitespace (Optional[bool]): Whether to collapse whitespace between block-level tags into
+            a single line break or space. Whitespace next to text or inline elements, and inside `<pre>`,
+            `<textarea>`, `<script>` and `<style>` is kept. Defaults to None.
 
     Returns:
-        A tuple containing:
-            - The transformed HTML string
-            - A dictionary mapping captured attribute values to lists of attributes that were added
-              to those elements. Only returned if watch_on_attribute is set, otherwise empty dict.
+        HtmlTransformResult with the transformed HTML, the captured attributes, and, if `source_map`
+        is set, a JSON-serializable dictionary
+        `{"version": 1, "mappings": [[output_start, output_end, source_start, source_end], ...]}`.
+        The result can also be unpacked as the `(html, captured)` tuple, or
+        `(html, captured, source_map)` if `source_map` is set.
 
     Example:
         >>> html = '<div><p>Hello</p></div>'
-        >>> set_html_attributes(html, ['data-root-id'], ['data-v-123'])
+        >>> set_html_attributes(html, ['data-root-id'], ['data-v-123']).html
         '<div data-root-id="" data-v-123=""><p data-v-123="">Hello</p></div>'
 
     Raises:
-        ValueError: If the HTML is malformed or cannot be parsed.
+        HtmlTransformError: If the HTML is malformed or cannot be parsed. Subclass of `ValueError`.
+
+    Warns:
+        DjcHtmlRecoveryWarning: For each unclosed tag, unexpected end tag, or duplicate attribute.
+            Elements with optional end tags, e.g. `<li>` or `<p>`, are not reported as unclosed.
+            Not issued again for results returned from the cache.
+    """
+    ...
+
+def set_html_attributes_many(
+    htmls: List[str],
+    root_attributes: List[str],
+    all_attributes: List[str],
+    *,
+    check_end_names: Optional[bool] = None,
+    watch_on_attribute: Optional[str] = None,
+    parallel: Optional[bool] = None,
+    num_threads: Optional[int] = None,
+    attribute_order: Optional[Union[Literal["original", "alphabetical"], List[str]]] = None,
+    repair_misnested: Optional[bool] = None,
+    xml: Optional[bool] = None,
+    csrf_token: Optional[str] = None,
+    htmx_attributes: Optional[Dict[str, Any]] = None,
+    htmx_selectors: Optional[List[str]] = None,
+    preserve_directives: Optional[bool] = None,
+    strip_comments: Optional[bool] = None,
+    keep_comments: Optional[List[str]] = None,
+    collapse_whitespace: Optional[bool] = None,
+    source_map: Optional[bool] = None,
+) -> List[HtmlTransformResult]:
+    """
+    Transform many HTML strings by adding the same attributes to their elements.
+
+    Same as calling `set_html_attributes()` for each of the HTML strings, but the GIL is released
+    while the HTML is transformed. With `parallel=True`, the HTML strings are transformed
+    in parallel on a thread pool, e.g. to precompile all templates of a large project.
+
+    Args:
+        htmls (List[str]): The HTML strings to transform.
+        root_attributes (List[str]): List of attribute names to add to root elements only.
+        all_attributes (List[str]): List of attribute names to add to all elements.
+        check_end_names (Optional[bool]): Whether to validate matching of end tags. Defaults to None.
+        watch_on_attribute (Optional[str]): If set, captures which attributes were added to elements with this attribute.
+        parallel (Optional[bool]): Whether to transform the HTML strings in parallel. Defaults to None.
+        num_threads (Optional[int]): Number of threads to use if `parallel` is set. Defaults to the number of CPUs.
+        attribute_order (Optional[Union[str, List[str]]]): Same as in `set_html_attributes()`.
+        repair_misnested (Optional[bool]): Same as in `set_html_attributes()`.
+        xml (Optional[bool]): Same as in `set_html_attributes()`.
+        csrf_token (Optional[str]): Same as in `set_html_attributes()`.
+        htmx_attributes (Optional[Dict[str, Any]]): Same as in `set_html_attributes()`.
+        htmx_selectors (Optional[List[str]]): Same as in `set_html_attributes()`.
+        preserve_directives (Optional[bool]): Same as in `set_html_attributes()`.
+        strip_comments (Optional[bool]): Same as in `set_html_attributes()`.
+        keep_comments (Optional[List[str]]): Same as in `set_html_attributes()`.
+        collapse_whitespace (Optional[bool]): Same as in `set_html_attributes()`.
+        source_map (Optional[bool]): Same as in `set_html_attributes()`.
+
+    Returns:
+        For each HTML string, in the same order, the same HtmlTransformResult
+        as returned by `set_html_attributes()`.
+
+    Example:
+        >>> results = set_html_attributes_many(['<div>A</div>', '<p>B</p>'], [], ['data-v-123'], parallel=True)
+        >>> [html for html, captured in results]
+        ['<div data-v-123="">A</div>', '<p data-v-123="">B</p>']
+
+    Raises:
+        HtmlTransformError: If any of the HTML strings is malformed or cannot be parsed. Subclass of `ValueError`.
+    """
+    ...
+
+def transform_html_file(
+    path: Union[str, os.PathLike[str]],
+    root_attributes: List[str],
+    all_attributes: List[str],
+    *,
+    check_end_names: Optional[bool] = None,
+    watch_on_attribute: Optional[str] = None,
+    attribute_order: Optional[Union[Literal["original", "alphabetical"], List[str]]] = None,
+    repair_misnested: Optional[bool] = None,
+    xml: Optional[bool] = None,
+    csrf_token: Optional[str] = None,
+    htmx_attributes: Optional[Dict[str, Any]] = None,
+    htmx_selectors: Optional[List[str]] = None,
+    preserve_directives: Optional[bool] = None,
+    strip_comments: Optional[bool] = None,
+    keep_comments: Optional[List[str]] = None,
+    collapse_whitespace: Optional[bool] = None,
+    source_map: Optional[bool] = None,
+) -> HtmlTransformResult:
+    """
+    Read a HTML file and transform it by adding attributes to the elements.
+
+    The file is read and decoded in Rust, so its content doesn't have to go through Python.
+    The encoding is detected from the byte order mark (BOM) or from the `<meta charset>`
+    declaration, and defaults to UTF-8.
+
+    Errors and warnings about the HTML start with the path, and have the `filename` attribute set.
+
+    Args:
+        path (Union[str, os.PathLike[str]]): Path to the HTML file.
+        root_attributes (List[str]): List of attribute names to add to root elements only.
+        all_attributes (List[str]): List of attribute names to add to all elements.
+        check_end_names (Optional[bool]): Whether to validate matching of end tags. Defaults to None.
+        watch_on_attribute (Optional[str]): If set, captures which attributes were added to elements with this attribute.
+        attribute_order (Optional[Union[str, List[str]]]): Same as in `set_html_attributes()`.
+        repair_misnested (Optional[bool]): Same as in `set_html_attributes()`.
+        xml (Optional[bool]): Same as in `set_html_attributes()`.
+        csrf_token (Optional[str]): Same as in `set_html_attributes()`.
+        htmx_attributes (Optional[Dict[str, Any]]): Same as in `set_html_attributes()`.
+        htmx_selectors (Optional[List[str]]): Same as in `set_html_attributes()`.
+        preserve_directives (Optional[bool]): Same as in `set_html_attributes()`.
+        strip_comments (Optional[bool]): Same as in `set_html_attributes()`.
+        keep_comments (Optional[List[str]]): Same as in `set_html_attributes()`.
+        collapse_whitespace (Optional[bool]): Same as in `set_html_attributes()`.
+        source_map (Optional[bool]): Same as in `set_html_attributes()`.
+
+    Returns:
+        Same as `set_html_attributes()`.
+
+    Example:
+        >>> html, captured = transform_html_file('templates/button.html', ['data-root-id'], ['data-v-123'])
+
+    Raises:
+        OSError: If the file cannot be read, e.g. `FileNotFoundError`.
+        HtmlTransformError: If the HTML is malformed or cannot be parsed. Subclass of `ValueError`.
+    """
+    ...
+
+def configure_cache(maxsize: int) -> None:
+    """
+    Enable, resize or disable the in-memory LRU cache of `set_html_attributes()` results.
+
+    Production renders often transform the same HTML with the same attributes over and over.
+    With the cache enabled, such repeated calls return the cached result instead.
+    Results are keyed by a hash of the HTML and of all the other arguments, and keep the HTML
+    to check that a cached result is for the same input.
+    The cache is shared by all threads, and is disabled by default.
+
+    Args:
+        maxsize (int): Maximum number of cached results. Least recently used results are evicted
+            first. 0 disables the cache and drops all cached results.
+
+    Example:
+        >>> configure_cache(1024)
+        >>> html, captured = set_html_attributes('<div></div>', [], ['data-v-123'])
+        >>> html, captured = set_html_attributes('<div></div>', [], ['data-v-123'])
+        >>> cache_info()
+        {'hits': 1, 'misses': 1, 'maxsize': 1024, 'currsize': 1, 'heap_size': ...}
+    """
+    ...
+
+def cache_info() -> CacheInfo:
+    """
+    Get the statistics of the cache of `set_html_attributes()` results.
+
+    Returns:
+        Dictionary with keys `hits`, `misses`, `maxsize` and `currsize`,
+        same as `functools.lru_cache().cache_info()`. `maxsize` is 0 if the cache is disabled.
+        `heap_size` is the approximate number of bytes taken by the cached results.
+    """
+    ...
+
+def cache_clear() -> None:
+    """
+    Remove all results from the cache of `set_html_attributes()` results and reset its statistics.
+    """
+    ...
+
+def hash_html(html: str) -> str:
+    """
+    Fingerprint HTML, ignoring insignificant whitespace, e.g. to use as a cache key
+    for transformed fragments.
+
+    Runs of whitespace in text and between attributes are collapsed. Attribute values, comments,
+    and content of `<pre>`, `<script>`, `<style>` and `<textarea>` are hashed as is.
+
+    Args:
+        html (str): The HTML string to hash.
+
+    Returns:
+        str: 128-bit xxHash (XXH3) of the normalized HTML, as 32 hexadecimal characters.
+
+    Example:
+        >>> hash_html('<div>\n  <p>Hi</p>\n</div>') == hash_html('<div> <p>Hi</p> </div>')
+        True
+    """
+    ...
+
+def hash_template(source: str) -> str:
+    """
+    Fingerprint Django template source, ignoring insignificant whitespace, e.g. to use
+    as a cache key for compiled templates.
+
+    Same as `hash_html()`, but also ignores whitespace inside `{{ }}` and `{% %}` tags.
+    String literals inside tags, `{# #}` comments and the content of `{% verbatim %}` blocks are hashed as is.
+
+    Args:
+        source (str): The template source to hash.
+
+    Returns:
+        str: 128-bit xxHash (XXH3) of the normalized source, as 32 hexadecimal characters.
+
+    Example:
+        >>> hash_template('{{ name }}') == hash_template('{{name}}')
+        True
+    """
+    ...
+
+def extract_html_assets(html: str) -> List[HtmlAsset]:
+    """
+    Find the assets that the HTML references or contains, e.g. to build a manifest
+    of the JS and CSS dependencies of components.
+
+    Recognizes `<script src>`, `<link href>`, `<img src>`, and inline `<script>` and `<style>` blocks.
+    Content of `<script>` and `<style>` is not parsed as HTML.
+
+    Args:
+        html (str): The HTML string to scan. Can be a fragment or full document.
+
+    Returns:
+        The assets in the order they appear in the HTML. Each has keys:
+            - `kind`: One of `script`, `link`, `image`, `inline_script`, `inline_style`
+            - `url`: Value of the `src` or `href` attribute, `None` for inline assets
+            - `rel`: Value of the `rel` attribute of `<link>`, e.g. `stylesheet`
+            - `content`: Content of inline assets
+            - `hash`: 128-bit xxHash (XXH3) of the content of inline assets, as 32 hexadecimal characters
+            - `start`, `end`: Position of the start tag
+
+    Example:
+        >>> assets = extract_html_assets('<script src="app.js"></script><style>p { color: red }</style>')
+        >>> [(a["kind"], a["url"]) for a in assets]
+        [('script', 'app.js'), ('inline_style', None)]
+
+    Raises:
+        HtmlTransformError: If the HTML is malformed or cannot be parsed. Subclass of `ValueError`.
     """
     ...
 
-__all__ = ["set_html_attributes"]
+__all__ = [
+    "CacheInfo",
+    "DjcHtmlRecoveryWarning",
+    "HtmlAsset",
+    "HtmlTransformError",
+    "HtmlTransformResult",
+    "HtmlTransformStats",
+    "SourceMap",
+    "cache_clear",
+    "cache_info",
+    "configure_cache",
+    "extract_html_assets",
+    "hash_html",
+    "hash_template",
+    "set_html_attributes",
+    "set_html_attributes_many",
+    "transform_html_file",
+]
@@ -0,0 +1,45 @@
+from typing import List, Literal, TypedDict
+
+class JsDependency(TypedDict):
+    specifier: str
+    kind: Literal["import", "side_effect", "dynamic", "reexport"]
+    specifier_kind: Literal["bare", "relative", "absolute", "url"]
+    names: List[str]
+    start: int
+    end: int
+    specifier_start: int
+    specifier_end: int
+
+def extract_js_dependencies(js: str) -> List[JsDependency]:
+    """
+    Find all modules that the JS imports or re-exports from.
+
+    Recognizes static imports (`import x from "spec"`), side-effect imports (`import "spec"`),
+    dynamic imports with a literal specifier (`import("spec")`) and re-exports
+    (`export { a } from "spec"`). Text inside strings, comments, template literals
+    and regexes is ignored.
+
+    Args:
+        js (str): The JavaScript source code.
+
+    Returns:
+        The dependencies in the order they appear in the source. Each has keys:
+            - `specifier`: The module specifier, e.g. `./utils.js`
+            - `kind`: One of `import`, `side_effect`, `dynamic`, `reexport`
+            - `specifier_kind`: One of `bare`, `relative`, `absolute`, `url`.
+              Only bare specifiers are resolved through an import map.
+            - `names`: Names imported from the module, `*` for namespace imports
+            - `start`, `end`: Position of the whole statement
+            - `specifier_start`, `specifier_end`: Position of the specifier string, including quotes
+
+    Example:
+        >>> deps = extract_js_dependencies('import { a } from "lib";')
+        >>> deps[0]["specifier"], deps[0]["specifier_kind"], deps[0]["names"]
+        ('lib', 'bare', ['a'])
+
+    Raises:
+        ValueError: If the JS cannot be tokenized, e.g. has unclosed strings or comments.
+    """
+    ...
+
+__all__ = ["JsDependency", "extract_js_dependencies"]
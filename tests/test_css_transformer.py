@@ -0,0 +1,70 @@
+import pytest
+
+from djc_core import minify_css, scope_css
+
+
+def test_simple_selectors():
+    css = ".btn { color: red; }\ndiv, .card p { margin: 0 }"
+    result = scope_css(css, "data-djc-c1a2")
+    expected = ".btn[data-djc-c1a2] { color: red; }\ndiv[data-djc-c1a2], .card p[data-djc-c1a2] { margin: 0 }"
+    assert result == expected
+
+
+def test_combinators_and_pseudo_selectors():
+    css = ".a > .b:hover::before, .c + :is(.d, .e) {}"
+    result = scope_css(css, "data-djc-c1a2")
+    expected = ".a > .b[data-djc-c1a2]:hover::before, .c + [data-djc-c1a2]:is(.d, .e) {}"
+    assert result == expected
+
+
+def test_at_rules():
+    css = """
+        @media (min-width: 600px) {
+            .a { color: red; }
+        }
+        @keyframes spin {
+            from { transform: rotate(0deg); }
+            to { transform: rotate(360deg); }
+        }
+    """
+    result = scope_css(css, "data-djc-c1a2")
+    expected = """
+        @media (min-width: 600px) {
+            .a[data-djc-c1a2] { color: red; }
+        }
+        @keyframes spin {
+            from { transform: rotate(0deg); }
+            to { transform: rotate(360deg); }
+        }
+    """
+    assert result == expected
+
+
+def test_invalid_css():
+    with pytest.raises(ValueError, match="Unclosed block"):
+        scope_css(".a { color: red;", "data-djc-c1a2")
+
+
+def test_minify():
+    css = """
+        /* Buttons */
+        .btn > p,
+        .card .title:hover {
+            margin: 0px 0.50em;
+            width: calc(100% - 2px);
+        }
+    """
+    result = minify_css(css)
+    expected = ".btn>p,.card .title:hover{margin:0 .5em;width:calc(100% - 2px)}"
+    assert result == expected
+
+
+def test_minify_scoped():
+    css = ".btn > p { color: red; }"
+    result = minify_css(scope_css(css, "data-djc-c1a2"))
+    assert result == ".btn>p[data-djc-c1a2]{color:red}"
+
+
+def test_minify_invalid_css():
+    with pytest.raises(ValueError, match="Unclosed comment"):
+        minify_css(".a {} /* unclosed")
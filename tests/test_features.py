@@ -0,0 +1,37 @@
+import inspect
+import re
+
+import djc_core
+from djc_core import features, set_html_attributes, set_html_attributes_many, transform_html_file
+
+
+def test_features():
+    info = features()
+
+    assert re.match(r"^\d+\.\d+\.\d+", info["version"])
+    assert info["features"] == {"cache": True, "parallel": True, "wasm": False}
+
+
+def test_features_functions():
+    functions = features()["functions"]
+
+    assert functions == sorted(functions)
+    assert "set_html_attributes" in functions
+    assert "features" in functions
+    # Exceptions and warnings are classes, not functions
+    assert "HtmlTransformError" not in functions
+    for name in functions:
+        assert inspect.signature(getattr(djc_core, name)) is not None
+
+
+def test_features_options():
+    options = features()["options"]
+
+    assert "xml" in options
+    for function, own_arguments in [
+        (set_html_attributes, ["html", "root_attributes", "all_attributes"]),
+        (set_html_attributes_many, ["htmls", "root_attributes", "all_attributes", "parallel", "num_threads"]),
+        (transform_html_file, ["path", "root_attributes", "all_attributes"]),
+    ]:
+        arguments = set(inspect.signature(function).parameters) - set(own_arguments)
+        assert set(options) == arguments
@@ -0,0 +1,50 @@
+import pytest
+
+from djc_core import hash_html, hash_template
+
+
+def test_hash_html_format():
+    digest = hash_html("<p>Hi</p>")
+    assert len(digest) == 32
+    int(digest, 16)
+    assert hash_html("<p>Hi</p>") == digest
+
+
+@pytest.mark.parametrize(
+    "a, b",
+    [
+        ("<div>\n  <p>Hi</p>\n</div>\n", "<div> <p>Hi</p> </div>"),
+        ('<div   class="a"\n  id = "b" >Hi</div >', '<div class="a" id="b">Hi</div>'),
+        ("<br />", "<br/>"),
+        ("<p>Ahoj   světe</p>", "<p>Ahoj světe</p>"),
+    ],
+)
+def test_hash_html_ignores_whitespace(a, b):
+    assert hash_html(a) == hash_html(b)
+
+
+@pytest.mark.parametrize(
+    "a, b",
+    [
+        ("<p>Hi</p>", "<p>Hello</p>"),
+        ('<p class="a b">', '<p class="a  b">'),
+        ("<pre>a b</pre>", "<pre>a\n b</pre>"),
+        ("<script>let a = 'x y'</script>", "<script>let a = 'x  y'</script>"),
+        ("<p>Hi</p>", "<p> Hi</p>"),
+    ],
+)
+def test_hash_html_significant_whitespace(a, b):
+    assert hash_html(a) != hash_html(b)
+
+
+def test_hash_template():
+    assert hash_template("{{ name }}") == hash_template("{{name}}")
+    assert hash_template("{%  if x  %}\n  {{ x }}\n{% endif %}") == hash_template("{% if x %} {{x}} {% endif %}")
+    assert hash_template('<p title="{% trans "Hi" %}">') == hash_template('<p title="{%trans "Hi"%}">')
+    # String literals, comments and verbatim blocks are kept
+    assert hash_template('{{ x|default:"a b" }}') != hash_template('{{ x|default:"a  b" }}')
+    assert hash_template("{# a b #}") != hash_template("{# a  b #}")
+    verbatim = "{% verbatim %}{{ a  }}{% endverbatim %}"
+    assert hash_template(verbatim) != hash_template("{% verbatim %}{{ a }}{% endverbatim %}")
+    # Plain HTML hashing doesn't know about template tags
+    assert hash_html("{{ name }}") != hash_html("{{name}}")
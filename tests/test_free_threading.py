@@ -0,0 +1,44 @@
+# Stress tests that call the Rust functions from many threads at once.
+# On free-threaded (no-GIL) Python builds these run truly in parallel.
+
+import os
+import sys
+import sysconfig
+from concurrent.futures import ThreadPoolExecutor
+
+from djc_core import extract_js_dependencies, minify_css, scope_css, set_html_attributes
+
+NUM_THREADS = 16
+NUM_ITERATIONS = 200
+
+
+def _run_in_threads(fn, inputs):
+    expected = [fn(inp) for inp in inputs]
+    with ThreadPoolExecutor(max_workers=NUM_THREADS) as executor:
+        for _ in range(NUM_ITERATIONS // 10):
+            results = list(executor.map(fn, inputs * 10))
+            assert results == expected * 10
+
+
+def test_gil_not_reenabled():
+    # Importing a module that doesn't declare free-threading support re-enables the GIL
+    if sysconfig.get_config_var("Py_GIL_DISABLED") and os.environ.get("PYTHON_GIL") != "1":
+        assert not sys._is_gil_enabled()
+
+
+def test_set_html_attributes_threads():
+    inputs = [f'<div data-id="{i}"><p>Item {i}</p><br></div>' for i in range(NUM_THREADS)]
+    _run_in_threads(
+        lambda html: set_html_attributes(html, ["data-root"], ["data-v-123"], watch_on_attribute="data-id"),
+        inputs,
+    )
+
+
+def test_css_threads():
+    inputs = [f".item-{i} > p:hover {{ margin: 0px {i}px; }}" for i in range(NUM_THREADS)]
+    _run_in_threads(lambda css: minify_css(scope_css(css, "data-djc-c1a2")), inputs)
+
+
+def test_js_threads():
+    inputs = [f'import {{ a{i} }} from "./mod{i}.js"; export * from "lib{i}";' for i in range(NUM_THREADS)]
+    _run_in_threads(extract_js_dependencies, inputs)
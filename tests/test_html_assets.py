@@ -0,0 +1,64 @@
+import pytest
+
+from djc_core import extract_html_assets
+
+
+def test_extract_html_assets():
+    html = (
+        "<head>\n"
+        '  <link rel="stylesheet" href="/static/app.css?v=1&amp;x=2">\n'
+        '  <script type="module" src="/static/app.js"></script>\n'
+        "  <style>p { color: red }</style>\n"
+        "</head>\n"
+        '<body><img src="logo.png" /><img alt="no src">'
+        "<script>if (a < b) { document.write('</p>') }</script></body>"
+    )
+    assets = extract_html_assets(html)
+
+    assert [(a["kind"], a["url"], a["rel"]) for a in assets] == [
+        ("link", "/static/app.css?v=1&x=2", "stylesheet"),
+        ("script", "/static/app.js", None),
+        ("inline_style", None, None),
+        ("image", "logo.png", None),
+        ("inline_script", None, None),
+    ]
+    assert [a["content"] for a in assets] == [
+        None,
+        None,
+        "p { color: red }",
+        None,
+        "if (a < b) { document.write('</p>') }",
+    ]
+    for asset in assets:
+        assert html[asset["start"] : asset["end"]].startswith("<")
+
+
+def test_extract_html_assets_hash():
+    assets = extract_html_assets("<style>p {}</style><style>p {}</style><style>p  {}</style>")
+    hashes = [a["hash"] for a in assets]
+    assert len(hashes[0]) == 32
+    # Same content has the same hash
+    assert hashes[0] == hashes[1]
+    # Inline content is hashed as is, including whitespace
+    assert hashes[0] != hashes[2]
+
+
+def test_extract_html_assets_char_offsets():
+    html = '<p>Ahoj světe</p><img src="obrázek.png">'
+    [asset] = extract_html_assets(html)
+    assert asset["url"] == "obrázek.png"
+    assert html[asset["start"] : asset["end"]] == '<img src="obrázek.png">'
+
+
+def test_extract_html_assets_unquoted_attributes():
+    assets = extract_html_assets("<link rel=stylesheet href=s.css><script src=a.js></script><img src=x.png>")
+    assert [(a["kind"], a["url"], a["rel"]) for a in assets] == [
+        ("link", "s.css", "stylesheet"),
+        ("script", "a.js", None),
+        ("image", "x.png", None),
+    ]
+
+
+def test_extract_html_assets_invalid():
+    with pytest.raises(ValueError):
+        extract_html_assets('<img src="a.png>')
@@ -146,3 +146,20 @@ def test_whitespace_preservation():
         <span data-all=""> Text with spaces </span>
     </div>"""
     assert result == expected
+
+
+def test_source_map():
+    html = "<div><p>Čau</p><br></div>"
+    result, _, source_map = set_html_attributes(html, ["data-root"], ["data-v-123"], source_map=True)
+    assert result == '<div data-root="" data-v-123=""><p data-v-123="">Čau</p><br data-v-123=""/></div>'
+    assert source_map["version"] == 1
+
+    # Positions are in characters, not bytes
+    pairs = [(result[o_start:o_end], html[s_start:s_end]) for o_start, o_end, s_start, s_end in source_map["mappings"]]
+    assert pairs == [
+        ('<div data-root="" data-v-123="">', "<div>"),
+        ('<p data-v-123="">', "<p>"),
+        ("Čau</p>", "Čau</p>"),
+        ('<br data-v-123=""/>', "<br>"),
+        ("</div>", "</div>"),
+    ]
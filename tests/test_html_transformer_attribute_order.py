@@ -0,0 +1,42 @@
+import pytest
+
+from djc_core import set_html_attributes
+
+HTML = '<div id="x" class="a" hidden><input type="text" disabled></div>'
+
+
+def test_original_order():
+    result = set_html_attributes(HTML, [], ["data-v"])
+    assert result.html == set_html_attributes(HTML, [], ["data-v"], attribute_order="original").html
+    assert result.html == '<div id="x" class="a" hidden data-v=""><input type="text" disabled data-v=""/></div>'
+
+
+def test_alphabetical_order():
+    result = set_html_attributes(HTML, ["data-root"], ["data-v"], attribute_order="alphabetical")
+    assert result.html == (
+        '<div class="a" data-root="" data-v="" hidden="" id="x"><input data-v="" disabled="" type="text"/></div>'
+    )
+
+
+def test_priority_order():
+    result = set_html_attributes(HTML, [], ["data-v"], attribute_order=["data-v", "type"])
+    assert result.html == (
+        '<div data-v="" id="x" class="a" hidden=""><input data-v="" type="text" disabled=""/></div>'
+    )
+
+
+def test_same_output_for_reordered_input():
+    other = '<div hidden class="a" id="x"><input disabled type="text"></div>'
+    result = set_html_attributes(HTML, [], ["data-v"], attribute_order="alphabetical")
+    assert result.html == set_html_attributes(other, [], ["data-v"], attribute_order="alphabetical").html
+
+
+def test_untouched_elements_keep_order():
+    # Only the root element gets attributes, so the nested element is left as it is
+    result = set_html_attributes("<div b a><p d c></p></div>", ["data-root"], [], attribute_order="alphabetical")
+    assert result.html == '<div a="" b="" data-root=""><p d c></p></div>'
+
+
+def test_invalid_attribute_order():
+    with pytest.raises(ValueError, match="attribute_order must be 'original', 'alphabetical'"):
+        set_html_attributes(HTML, [], ["data-v"], attribute_order="random")
@@ -0,0 +1,81 @@
+import warnings
+
+import pytest
+
+from djc_core import DjcHtmlRecoveryWarning, cache_clear, cache_info, configure_cache, set_html_attributes
+
+
+@pytest.fixture(autouse=True)
+def cache():
+    configure_cache(16)
+    cache_clear()
+    yield
+    configure_cache(0)
+
+
+def test_disabled_cache():
+    configure_cache(0)
+    set_html_attributes("<div></div>", [], ["data-v"])
+    set_html_attributes("<div></div>", [], ["data-v"])
+
+    assert cache_info() == {"hits": 0, "misses": 0, "maxsize": 0, "currsize": 0, "heap_size": 0}
+
+
+def test_cache_hit_returns_same_result():
+    first = set_html_attributes('<div data-id="1"></div>', ["data-root"], ["data-v"], watch_on_attribute="data-id")
+    second = set_html_attributes('<div data-id="1"></div>', ["data-root"], ["data-v"], watch_on_attribute="data-id")
+
+    assert first == second
+    info = cache_info()
+    heap_size = info.pop("heap_size")
+    assert info == {"hits": 1, "misses": 1, "maxsize": 16, "currsize": 1}
+    assert heap_size > len(first.html)
+
+
+def test_options_are_part_of_key():
+    html = "<div></div>"
+    assert set_html_attributes(html, [], ["data-a"])[0] == '<div data-a=""></div>'
+    assert set_html_attributes(html, [], ["data-b"])[0] == '<div data-b=""></div>'
+    assert set_html_attributes(html, ["data-a"], [])[0] == '<div data-a=""></div>'
+    assert len(set_html_attributes(html, [], ["data-a"], source_map=True)) == 3
+
+    assert cache_info()["misses"] == 4
+    assert cache_info()["hits"] == 0
+
+
+def test_errors_are_not_cached():
+    for _ in range(2):
+        with pytest.raises(ValueError):
+            set_html_attributes("<div></span>", [], [], check_end_names=True)
+
+    assert cache_info()["currsize"] == 0
+
+
+def test_eviction():
+    configure_cache(2)
+    for i in range(5):
+        set_html_attributes(f"<p>{i}</p>", [], ["data-v"])
+
+    assert cache_info()["currsize"] == 2
+
+    # Evicted results don't count
+    heap_size = cache_info()["heap_size"]
+    set_html_attributes("<p>5</p>", [], ["data-v"])
+    assert cache_info()["heap_size"] == heap_size
+
+    cache_clear()
+    assert cache_info()["heap_size"] == 0
+
+
+def test_cache_hit_does_not_warn_again():
+    with warnings.catch_warnings(record=True) as record:
+        warnings.simplefilter("always")
+        first = set_html_attributes("<div><span>Hi</div>", [], ["data-v"])
+        second = set_html_attributes("<div><span>Hi</div>", [], ["data-v"])
+
+    assert [str(w.message) for w in record if w.category is DjcHtmlRecoveryWarning] == [
+        "Unclosed tag <span> at line 1, column 6"
+    ]
+    assert len(first.warnings) == 1
+    assert second.html == first.html
+    assert second.warnings == []
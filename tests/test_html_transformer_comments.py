@@ -0,0 +1,37 @@
+import pytest
+
+from djc_core import set_html_attributes
+
+
+def test_kept_by_default():
+    result = set_html_attributes("<!-- note --><p>Hi</p>", [], ["data-v"])
+    assert result.html == '<!-- note --><p data-v="">Hi</p>'
+
+
+def test_stripped():
+    result = set_html_attributes("<!-- note --><p>Hi<!-- TODO --></p>", [], ["data-v"], strip_comments=True)
+    assert result.html == '<p data-v="">Hi</p>'
+
+
+def test_conditional_comments_and_markers_kept():
+    html = "<!--[if lt IE 9]><script src='html5shiv.js'></script><![endif]--><!-- _RENDERED card_a1b2c3,x -->"
+    assert set_html_attributes(html, [], [], strip_comments=True).html == html
+
+
+@pytest.mark.parametrize(
+    "html, expected",
+    [
+        ("<div> <!-- c --> <p>Hi</p></div>", "<div> <p>Hi</p></div>"),
+        ("<p>Hi\n  <!-- a -->\n  <!-- b -->\n  there</p>", "<p>Hi\n  there</p>"),
+        ("<p>Hi<!-- c --> there</p>", "<p>Hi there</p>"),
+        ("<pre>a <!-- c --> b</pre>", "<pre>a  b</pre>"),
+    ],
+)
+def test_whitespace_around_stripped_comments(html, expected):
+    assert set_html_attributes(html, [], [], strip_comments=True).html == expected
+
+
+def test_keep_comments():
+    html = "<!-- license: MIT --><!-- note --><!--[if IE]>x<![endif]-->"
+    result = set_html_attributes(html, [], [], strip_comments=True, keep_comments=["license:*"])
+    assert result.html == "<!-- license: MIT -->"
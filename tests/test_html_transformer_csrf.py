@@ -0,0 +1,29 @@
+import pytest
+
+from djc_core import set_html_attributes
+
+CSRF_INPUT = '<input type="hidden" name="csrfmiddlewaretoken" value="abc"/>'
+
+
+def test_inserted_into_post_forms():
+    result = set_html_attributes('<form method="post"><button>Send</button></form>', [], [], csrf_token="abc")
+    assert result.html == f'<form method="post"><button>Send</button>{CSRF_INPUT}</form>'
+
+
+def test_not_inserted_twice():
+    html = '<form method="post"><input type="hidden" name="csrfmiddlewaretoken" value="abc"/></form>'
+    result = set_html_attributes(html, [], [], csrf_token="abc")
+    assert result.html == html
+
+
+@pytest.mark.parametrize("html", ['<form method="get"></form>', "<form></form>"])
+def test_get_forms_left_alone(html):
+    assert set_html_attributes(html, [], [], csrf_token="abc").html == html
+
+
+def test_token_escaped():
+    result = set_html_attributes('<form method="post"></form>', [], ["data-v"], csrf_token='"><script>')
+    assert result.html == (
+        '<form method="post" data-v="">'
+        '<input type="hidden" name="csrfmiddlewaretoken" value="&quot;&gt;&lt;script&gt;"/></form>'
+    )
@@ -0,0 +1,33 @@
+import pytest
+
+from djc_core import set_html_attributes
+
+DIRECTIVES = [
+    '@click="open = !open"',
+    ":class=\"{ 'active': count > 0 }\"",
+    "x-on:click.prevent",
+    'hx-on::after-request="this.reset()"',
+    "x-bind:aria-expanded='open'",
+    "v-on:keyup.enter=submit",
+    "#default",
+]
+
+
+@pytest.mark.parametrize("directive", DIRECTIVES)
+def test_kept_as_written(directive):
+    result = set_html_attributes(f"<div {directive}><p {directive}></p></div>", ["data-root"], ["data-v"])
+    assert result.html == f'<div {directive} data-root="" data-v=""><p {directive} data-v=""></p></div>'
+
+
+@pytest.mark.parametrize("directive", DIRECTIVES)
+def test_kept_as_written_with_attribute_order(directive):
+    result = set_html_attributes(
+        f'<div id="x" {directive}></div>', [], ["data-v"], attribute_order=["id"], preserve_directives=True
+    )
+    assert result.html == f'<div id="x" {directive} data-v=""></div>'
+
+
+def test_rewritten_with_attribute_order():
+    html = "<div x-on:click.prevent x-bind:title='a'></div>"
+    result = set_html_attributes(html, [], ["data-v"], attribute_order="alphabetical")
+    assert result.html == '<div data-v="" x-bind:title="a" x-on:click.prevent=""></div>'
@@ -0,0 +1,59 @@
+import os
+import tempfile
+
+import pytest
+
+from djc_core import (
+    HtmlTransformError,
+    extract_html_assets,
+    set_html_attributes,
+    set_html_attributes_many,
+    transform_html_file,
+)
+
+
+def test_error_position():
+    html = "<div>\n  <p>Ahoj světe</span>\n</div>"
+    with pytest.raises(HtmlTransformError) as exc_info:
+        set_html_attributes(html, [], [], check_end_names=True)
+
+    error = exc_info.value
+    assert (error.line, error.column) == (2, 16)
+    # Offset is in characters, so it can be used to index into the string
+    assert html[error.offset :].startswith("</span>")
+    assert error.source_snippet == "  <p>Ahoj světe</span>"
+    assert str(error).endswith("at line 2, column 16")
+    assert error.filename is None
+
+
+def test_error_is_value_error():
+    with pytest.raises(ValueError):
+        set_html_attributes("<div></span>", [], [], check_end_names=True)
+
+
+def test_error_many():
+    htmls = ["<div>A</div>", "<div>B</span>"]
+    with pytest.raises(HtmlTransformError, match="at index 1") as exc_info:
+        set_html_attributes_many(htmls, [], [], check_end_names=True)
+    assert htmls[1][exc_info.value.offset :] == "</span>"
+
+
+def test_error_file():
+    fd, path = tempfile.mkstemp(suffix=".html")
+    with os.fdopen(fd, "wb") as f:
+        f.write("<p>Ahoj světe\n<div></span>".encode("utf-8"))
+    try:
+        with pytest.raises(HtmlTransformError) as exc_info:
+            transform_html_file(path, [], [], check_end_names=True)
+    finally:
+        os.remove(path)
+
+    error = exc_info.value
+    assert (error.line, error.column, error.offset) == (2, 6, 19)
+    assert error.source_snippet == "<div></span>"
+
+
+def test_error_assets():
+    with pytest.raises(HtmlTransformError) as exc_info:
+        extract_html_assets('<p>\n<img src="a.png>')
+    assert exc_info.value.line == 2
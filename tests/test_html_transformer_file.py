@@ -0,0 +1,104 @@
+import os
+import pathlib
+import tempfile
+import warnings
+
+import pytest
+
+from djc_core import DjcHtmlRecoveryWarning, HtmlTransformError, transform_html_file
+
+
+def _write(content: bytes) -> str:
+    fd, path = tempfile.mkstemp(suffix=".html")
+    with os.fdopen(fd, "wb") as f:
+        f.write(content)
+    return path
+
+
+@pytest.mark.parametrize(
+    "content, expected",
+    [
+        ("<p>Ahoj světe</p>".encode("utf-8"), '<p data-v="">Ahoj světe</p>'),
+        # BOM is removed
+        ("\ufeff<p>é</p>".encode("utf-8"), '<p data-v="">é</p>'),
+        ("\ufeff<p>é</p>".encode("utf-16-le"), '<p data-v="">é</p>'),
+        # Encoding declaration
+        (
+            '<meta charset="iso-8859-2"><p>ą</p>'.encode("iso-8859-2"),
+            '<meta charset="iso-8859-2" data-v=""/><p data-v="">ą</p>',
+        ),
+    ],
+)
+def test_decoding(content, expected):
+    path = _write(content)
+    try:
+        html, captured = transform_html_file(path, [], ["data-v"])
+    finally:
+        os.remove(path)
+
+    assert html == expected
+    assert captured == {}
+
+
+def test_watch_attribute():
+    path = _write(b'<div data-id="1"><p>Hi</p></div>')
+    try:
+        html, captured = transform_html_file(path, ["data-root"], ["data-v"], watch_on_attribute="data-id")
+    finally:
+        os.remove(path)
+
+    assert html == '<div data-id="1" data-root="" data-v=""><p data-v="">Hi</p></div>'
+    assert captured == {"1": ["data-root", "data-v"]}
+
+
+def test_missing_file():
+    with pytest.raises(FileNotFoundError):
+        transform_html_file("/nonexistent/template.html", [], [])
+
+
+def test_malformed_html():
+    path = _write(b"<div></span>")
+    try:
+        with pytest.raises(ValueError):
+            transform_html_file(path, [], [], check_end_names=True)
+    finally:
+        os.remove(path)
+
+
+def test_path_like():
+    path = _write(b"<p>Hi</p>")
+    try:
+        html, captured = transform_html_file(pathlib.Path(path), [], ["data-v"])
+    finally:
+        os.remove(path)
+
+    assert html == '<p data-v="">Hi</p>'
+
+
+def test_filename_in_error():
+    path = _write(b"<div>\n<p></span>")
+    try:
+        with pytest.raises(HtmlTransformError) as exc_info:
+            transform_html_file(pathlib.Path(path), [], [], check_end_names=True)
+    finally:
+        os.remove(path)
+
+    error = exc_info.value
+    assert error.filename == path
+    assert str(error).startswith(f"{path}: ")
+    assert (error.line, error.column) == (2, 4)
+
+
+def test_filename_in_warning():
+    path = _write(b"<div><span>Hi</div>")
+    try:
+        with warnings.catch_warnings(record=True) as record:
+            warnings.simplefilter("always")
+            transform_html_file(path, [], [])
+    finally:
+        os.remove(path)
+
+    [warning] = [w.message for w in record if issubclass(w.category, DjcHtmlRecoveryWarning)]
+    assert warning.filename == path
+    assert str(warning) == f"{path}: Unclosed tag <span> at line 1, column 6"
+
@@ -0,0 +1,46 @@
+import pytest
+
+from djc_core import set_html_attributes
+
+
+def test_added_to_root_elements():
+    result = set_html_attributes(
+        '<div><p>Hi</p></div><span hx-target="#x"></span>',
+        [],
+        ["data-v"],
+        htmx_attributes={"hx-target": "this", "hx-swap": "outerHTML"},
+    )
+    assert result.html == (
+        '<div data-v="" hx-target="this" hx-swap="outerHTML"><p data-v="">Hi</p></div>'
+        '<span hx-target="#x" data-v="" hx-swap="outerHTML"></span>'
+    )
+
+
+def test_json_values():
+    result = set_html_attributes(
+        "<div></div>",
+        [],
+        [],
+        htmx_attributes={"hx-headers": {"X-CSRFToken": 'a"b'}, "hx-vals": {"page": 2}},
+    )
+    assert result.html == (
+        '<div hx-headers="{&quot;X-CSRFToken&quot;: &quot;a\\&quot;b&quot;}" hx-vals="{&quot;page&quot;: 2}"></div>'
+    )
+
+
+def test_selectors():
+    result = set_html_attributes(
+        '<form><button class="save">Save</button><button>Cancel</button></form>',
+        [],
+        [],
+        htmx_attributes={"hx-post": "/save"},
+        htmx_selectors=["button.save", "#main"],
+    )
+    assert result.html == '<form><button class="save" hx-post="/save">Save</button><button>Cancel</button></form>'
+
+
+def test_invalid():
+    with pytest.raises(ValueError, match="must start with 'hx-' or 'data-hx-'"):
+        set_html_attributes("<div></div>", [], [], htmx_attributes={"class": "x"})
+    with pytest.raises(ValueError, match="Unsupported selector 'div > p'"):
+        set_html_attributes("<div></div>", [], [], htmx_attributes={"hx-get": "/"}, htmx_selectors=["div > p"])
@@ -0,0 +1,47 @@
+import pytest
+
+from djc_core import set_html_attributes, set_html_attributes_many
+
+
+HTMLS = [
+    '<div data-id="1"><p>Hello</p></div>',
+    "<span>Foo</span><br>",
+    '<ul data-id="2"><li>Bar</li></ul>',
+]
+
+
+@pytest.mark.parametrize("parallel", [False, True])
+def test_same_as_single(parallel):
+    results = set_html_attributes_many(
+        HTMLS,
+        ["data-root"],
+        ["data-v-123"],
+        watch_on_attribute="data-id",
+        parallel=parallel,
+    )
+
+    expected = [
+        set_html_attributes(html, ["data-root"], ["data-v-123"], watch_on_attribute="data-id")
+        for html in HTMLS
+    ]
+    assert results == expected
+
+
+@pytest.mark.parametrize("num_threads", [None, 1, 4])
+def test_parallel_keeps_order(num_threads):
+    htmls = [f"<div><p>{i}</p></div>" for i in range(500)]
+    results = set_html_attributes_many(htmls, [], ["data-v"], parallel=True, num_threads=num_threads)
+
+    assert [html for html, _ in results] == [f'<div data-v=""><p data-v="">{i}</p></div>' for i in range(500)]
+
+
+def test_empty():
+    assert set_html_attributes_many([], ["data-root"], ["data-v"], parallel=True) == []
+
+
+@pytest.mark.parametrize("parallel", [False, True])
+def test_error_reports_index(parallel):
+    htmls = ["<div>A</div>", "<div>B</span>"]
+
+    with pytest.raises(ValueError, match="at index 1"):
+        set_html_attributes_many(htmls, [], [], check_end_names=True, parallel=parallel)
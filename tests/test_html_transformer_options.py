@@ -0,0 +1,89 @@
+import inspect
+import os
+import tempfile
+
+import pytest
+
+from djc_core import set_html_attributes, set_html_attributes_many, transform_html_file
+
+# Arguments that only some of the functions take
+OWN_ARGUMENTS = {"html", "htmls", "path", "parallel", "num_threads"}
+
+
+def _transform_one(html, root_attributes, all_attributes, **kwargs):
+    return set_html_attributes(html, root_attributes, all_attributes, **kwargs)
+
+
+def _transform_many(html, root_attributes, all_attributes, **kwargs):
+    [result] = set_html_attributes_many([html], root_attributes, all_attributes, **kwargs)
+    return result
+
+
+def _transform_file(html, root_attributes, all_attributes, **kwargs):
+    fd, path = tempfile.mkstemp(suffix=".html")
+    with os.fdopen(fd, "wb") as f:
+        f.write(html.encode("utf-8"))
+    try:
+        return transform_html_file(path, root_attributes, all_attributes, **kwargs)
+    finally:
+        os.remove(path)
+
+
+TRANSFORMS = [_transform_one, _transform_many, _transform_file]
+
+# For each option, the HTML and the result with the option set
+OPTIONS = [
+    ({"check_end_names": True}, "<div></div>", '<div data-v=""></div>'),
+    ({"watch_on_attribute": "data-id"}, '<p data-id="1"></p>', '<p data-id="1" data-v=""></p>'),
+    ({"source_map": True}, "<p></p>", '<p data-v=""></p>'),
+    ({"attribute_order": "alphabetical"}, "<p id=x class=a></p>", '<p class="a" data-v="" id="x"></p>'),
+    ({"repair_misnested": True}, "<b><i>1</b>2", '<b data-v=""><i data-v="">1</i></b><i data-v="">2</i>'),
+    ({"xml": True}, "<Feed><link/></Feed>", '<Feed data-v=""><link data-v=""/></Feed>'),
+    (
+        {"csrf_token": "abc"},
+        '<form method="post"></form>',
+        '<form method="post" data-v=""><input type="hidden" name="csrfmiddlewaretoken" value="abc"/></form>',
+    ),
+    ({"htmx_attributes": {"hx-swap": "none"}}, "<p></p>", '<p data-v="" hx-swap="none"></p>'),
+    (
+        {"htmx_attributes": {"hx-get": "/"}, "htmx_selectors": ["i"]},
+        "<p><i></i></p>",
+        '<p data-v=""><i data-v="" hx-get="/"></i></p>',
+    ),
+    (
+        {"attribute_order": "alphabetical", "preserve_directives": True},
+        '<p x-on:click.prevent @click="a"></p>',
+        '<p @click="a" data-v="" x-on:click.prevent></p>',
+    ),
+    ({"strip_comments": True}, "<!-- a --><p></p>", '<p data-v=""></p>'),
+    ({"strip_comments": True, "keep_comments": ["a"]}, "<!--a--><!--b--><p></p>", '<!--a--><p data-v=""></p>'),
+    ({"collapse_whitespace": True}, "<div>\n  <p></p>\n</div>", '<div data-v="">\n<p data-v=""></p>\n</div>'),
+]
+
+
+def test_same_options():
+    def options(function, own_argument):
+        names = set(inspect.signature(function).parameters) - OWN_ARGUMENTS
+        assert own_argument in inspect.signature(function).parameters
+        return names
+
+    assert options(set_html_attributes, "html") == options(set_html_attributes_many, "htmls")
+    assert options(set_html_attributes, "html") == options(transform_html_file, "path")
+    # Every option is tested below
+    tested = {name for kwargs, _, _ in OPTIONS for name in kwargs}
+    assert options(set_html_attributes, "html") == tested | {"root_attributes", "all_attributes"}
+
+
+@pytest.mark.parametrize("transform", TRANSFORMS)
+@pytest.mark.parametrize("kwargs, html, expected", OPTIONS)
+def test_option(transform, kwargs, html, expected):
+    assert transform(html, [], ["data-v"], **kwargs).html == expected
+
+
+@pytest.mark.parametrize("transform", TRANSFORMS)
+def test_source_map(transform):
+    assert transform("<p></p>", [], ["data-v"]).source_map is None
+    assert transform("<p></p>", [], ["data-v"], source_map=True).source_map == {
+        "version": 1,
+        "mappings": [[0, 13, 0, 3], [13, 17, 3, 7]],
+    }
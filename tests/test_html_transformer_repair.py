@@ -0,0 +1,28 @@
+import pytest
+
+from djc_core import set_html_attributes
+
+
+def test_misnested_kept_by_default():
+    result = set_html_attributes("<b><i>1</b>2</i>", [], ["data-v"])
+    assert result.html == '<b data-v=""><i data-v="">1</b>2</i>'
+
+
+def test_formatting_elements_reopened():
+    result = set_html_attributes("<b><i>1</b>2</i>", [], ["data-v"], repair_misnested=True)
+    assert result.html == '<b data-v=""><i data-v="">1</i></b><i data-v="">2</i>'
+
+
+def test_unexpected_end_tags_removed():
+    result = set_html_attributes("<div>1</div></span>2", [], [], repair_misnested=True)
+    assert result.html == "<div>1</div>2"
+
+
+def test_unclosed_elements_closed():
+    result = set_html_attributes("<div><span>1", [], ["data-v"], repair_misnested=True)
+    assert result.html == '<div data-v=""><span data-v="">1</span></div>'
+
+
+def test_check_end_names_still_rejects():
+    with pytest.raises(ValueError):
+        set_html_attributes("<b><i>1</b>2</i>", [], [], check_end_names=True, repair_misnested=True)
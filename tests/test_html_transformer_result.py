@@ -0,0 +1,78 @@
+import warnings
+
+from djc_core import (
+    DjcHtmlRecoveryWarning,
+    HtmlTransformResult,
+    set_html_attributes,
+    set_html_attributes_many,
+)
+
+
+def test_result_attributes():
+    html = '<!-- c --><DIV data-id="1"><p>Hi<br></p></DIV><img/>'
+    result = set_html_attributes(html, ["data-root"], ["data-v"], watch_on_attribute="data-id")
+
+    assert isinstance(result, HtmlTransformResult)
+    assert result.html == (
+        '<!-- c --><DIV data-id="1" data-root="" data-v=""><p data-v="">Hi<br data-v=""/></p></DIV>'
+        '<img data-root="" data-v=""/>'
+    )
+    assert result.captured == {"1": ["data-root", "data-v"]}
+    assert result.source_map is None
+    assert result.roots == ["div", "img"]
+    assert result.stats == {"elements": 4, "attributes_added": 6}
+    assert result.warnings == []
+
+
+def test_result_warnings():
+    with warnings.catch_warnings(record=True):
+        warnings.simplefilter("always")
+        result = set_html_attributes("<div><span>Hi</div>", [], ["data-v"])
+
+    assert len(result.warnings) == 1
+    assert isinstance(result.warnings[0], DjcHtmlRecoveryWarning)
+    assert (result.warnings[0].line, result.warnings[0].column) == (1, 6)
+
+
+def test_result_as_tuple():
+    result = set_html_attributes("<p>Hi</p>", [], ["data-v"])
+    html, captured = result
+
+    assert (html, captured) == ('<p data-v="">Hi</p>', {})
+    assert len(result) == 2
+    assert result[0] == html
+    assert result[-1] == captured
+    assert result == (html, captured)
+    assert result == set_html_attributes("<p>Hi</p>", [], ["data-v"])
+    assert result != set_html_attributes("<p>Ho</p>", [], ["data-v"])
+    assert result != "<p data-v=\"\">Hi</p>"
+
+    result = set_html_attributes("<p>Hi</p>", [], ["data-v"], source_map=True)
+    html, captured, source_map = result
+    assert len(result) == 3
+    assert source_map is result.source_map
+    assert source_map["version"] == 1
+
+
+def test_result_iter_captured():
+    html = '<div data-id="1"><p data-id="2"></p><p data-id="1"></p></div>'
+    result = set_html_attributes(html, ["data-root"], ["data-v"], watch_on_attribute="data-id")
+
+    captured = result.iter_captured()
+    assert next(captured) == ("1", ["data-root", "data-v"])
+    assert list(captured) == [("2", ["data-v"]), ("1", ["data-v"])]
+    assert list(captured) == []
+    # Same value is captured once in the dict, with the attributes of the last element
+    assert result.captured == {"1": ["data-v"], "2": ["data-v"]}
+
+
+def test_result_many():
+    results = set_html_attributes_many(["<p>A</p>", "<br><i>B</i>"], ["data-root"], [])
+
+    assert [result.roots for result in results] == [["p"], ["br", "i"]]
+    assert [result.stats["attributes_added"] for result in results] == [1, 2]
+
+
+def test_result_repr():
+    result = set_html_attributes("<p>Hi</p>", [], [])
+    assert repr(result) == "HtmlTransformResult(html='<p>Hi</p>', captured={}, roots=['p'])"
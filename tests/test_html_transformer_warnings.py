@@ -0,0 +1,60 @@
+import warnings
+
+import pytest
+
+from djc_core import DjcHtmlRecoveryWarning, set_html_attributes, set_html_attributes_many
+
+
+def _transform_with_warnings(html: str):
+    with warnings.catch_warnings(record=True) as record:
+        warnings.simplefilter("always")
+        set_html_attributes(html, [], ["data-v-123"])
+    return [w.message for w in record if issubclass(w.category, DjcHtmlRecoveryWarning)]
+
+
+def test_well_formed_html_no_warnings():
+    assert _transform_with_warnings("<div><p>Hello</p><br></div>") == []
+
+
+def test_optional_end_tags_no_warnings():
+    assert _transform_with_warnings("<ul><li>a<li>b</ul><p>x") == []
+    assert _transform_with_warnings("<table><tr><td>1<td>2</table>") == []
+
+
+@pytest.mark.parametrize(
+    "html, expected, line, column",
+    [
+        ("<div><span>Hi</div>", "Unclosed tag <span> at line 1, column 6", 1, 6),
+        ('<p class="a" class="b">Hi</p>', "Duplicate attribute 'class' in <p> at line 1, column 1", 1, 1),
+    ],
+)
+def test_recovery_warning(html, expected, line, column):
+    messages = _transform_with_warnings(html)
+    assert [str(m) for m in messages] == [expected]
+    assert messages[0].line == line
+    assert messages[0].column == column
+
+
+def test_recovery_warning_unexpected_end_tag():
+    # `</p>` closes also the `<span>`, so `</i>` has nothing to close
+    messages = _transform_with_warnings("<p><span>Hi</p>\n  </i>")
+    assert [str(m) for m in messages] == [
+        "Unclosed tag <span> at line 1, column 4",
+        "Unexpected end tag </i> at line 2, column 3",
+    ]
+    assert (messages[1].line, messages[1].column) == (2, 3)
+
+
+def test_recovery_warning_many():
+    with warnings.catch_warnings(record=True) as record:
+        warnings.simplefilter("always")
+        set_html_attributes_many(["<p>A</p>", "<div>B"], [], ["data-v-123"])
+
+    assert [str(w.message) for w in record] == ["HTML at index 1: Unclosed tag <div> at line 1, column 1"]
+
+
+def test_recovery_warning_as_error():
+    with warnings.catch_warnings():
+        warnings.simplefilter("error", DjcHtmlRecoveryWarning)
+        with pytest.raises(DjcHtmlRecoveryWarning, match="Unclosed tag <span>"):
+            set_html_attributes("<div><span>Hi</div>", [], ["data-v-123"])
@@ -0,0 +1,29 @@
+import pytest
+
+from djc_core import set_html_attributes
+
+
+def test_kept_by_default():
+    html = "<div>\n    <p>Hi</p>\n</div>"
+    assert set_html_attributes(html, [], ["data-v"]).html == '<div data-v="">\n    <p data-v="">Hi</p>\n</div>'
+
+
+def test_collapsed_between_block_tags():
+    html = "<ul>\n    <li>A</li>\n    <li>B</li>\n</ul>\t\t<div></div>"
+    result = set_html_attributes(html, [], [], collapse_whitespace=True)
+    assert result.html == "<ul>\n<li>A</li>\n<li>B</li>\n</ul> <div></div>"
+
+
+@pytest.mark.parametrize(
+    "html, expected",
+    [
+        ("<p>\n  <span>A</span>\n  <b>B</b>\n</p>", "<p>\n  <span>A</span>\n  <b>B</b>\n</p>"),
+        (
+            "<div>\n  <pre>\n    <div>code</div>\n  </pre>\n</div>",
+            "<div>\n<pre>\n    <div>code</div>\n  </pre>\n</div>",
+        ),
+        ("<form>\n  <textarea>\n\n  </textarea>\n</form>", "<form>\n  <textarea>\n\n  </textarea>\n</form>"),
+    ],
+)
+def test_inline_and_preformatted_kept(html, expected):
+    assert set_html_attributes(html, [], [], collapse_whitespace=True).html == expected
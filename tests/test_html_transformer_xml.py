@@ -0,0 +1,40 @@
+import pytest
+
+from djc_core import HtmlTransformError, set_html_attributes
+
+
+def test_names_kept_as_they_are():
+    result = set_html_attributes("<svg><clipPath id='c'/></svg><Feed/>", ["data-root"], [], xml=True)
+    assert result.html == "<svg data-root=\"\"><clipPath id='c'/></svg><Feed data-root=\"\"/>"
+    assert result.roots == ["svg", "Feed"]
+
+
+def test_cdata_and_processing_instructions():
+    xml = '<?xml-stylesheet href="a.css"?><svg><script><![CDATA[ if (a < b) {} ]]></script></svg>'
+    result = set_html_attributes(xml, [], ["data-v"], xml=True)
+    assert result.html == (
+        '<?xml-stylesheet href="a.css"?><svg data-v=""><script data-v=""><![CDATA[ if (a < b) {} ]]></script></svg>'
+    )
+
+
+def test_no_void_elements():
+    rss = "<item><link>https://example.com</link></item>"
+    result = set_html_attributes(rss, [], ["data-v"], xml=True)
+    assert result.html == '<item data-v=""><link data-v="">https://example.com</link></item>'
+
+    # In HTML, `<link>` is a void element
+    result = set_html_attributes(rss, [], ["data-v"])
+    assert result.html == '<item data-v=""><link data-v=""/>https://example.com</item>'
+
+
+@pytest.mark.parametrize(
+    "xml, message",
+    [
+        ("<svg><br></svg>", "expected `</br>`"),
+        ("<Item></item>", "expected `</Item>`"),
+        ("<svg><g>", "Unclosed tag <g>"),
+    ],
+)
+def test_malformed(xml, message):
+    with pytest.raises(HtmlTransformError, match=message):
+        set_html_attributes(xml, [], [], xml=True)
@@ -0,0 +1,58 @@
+import pytest
+
+from djc_core import extract_js_dependencies
+
+
+def test_static_imports():
+    js = """
+        import x from "./x.js";
+        import { a, b as c } from 'lib';
+        import * as ns from "https://cdn.example.com/ns.js";
+        import "/static/side-effect.js";
+    """
+    deps = extract_js_dependencies(js)
+    summary = [(dep["specifier"], dep["kind"], dep["specifier_kind"], dep["names"]) for dep in deps]
+    assert summary == [
+        ("./x.js", "import", "relative", ["default"]),
+        ("lib", "import", "bare", ["a", "b"]),
+        ("https://cdn.example.com/ns.js", "import", "url", ["*"]),
+        ("/static/side-effect.js", "side_effect", "absolute", []),
+    ]
+
+
+def test_dynamic_imports_and_reexports():
+    js = """
+        export { a } from "./a.js";
+        export * from "./all.js";
+        const mod = await import("lib/mod.js");
+    """
+    deps = extract_js_dependencies(js)
+    summary = [(dep["specifier"], dep["kind"], dep["names"]) for dep in deps]
+    assert summary == [
+        ("./a.js", "reexport", ["a"]),
+        ("./all.js", "reexport", ["*"]),
+        ("lib/mod.js", "dynamic", []),
+    ]
+
+
+def test_ignores_strings_and_comments():
+    js = """
+        // import "./comment.js";
+        const s = 'import "./string.js"';
+        import "./real.js";
+    """
+    deps = extract_js_dependencies(js)
+    assert [dep["specifier"] for dep in deps] == ["./real.js"]
+
+
+def test_positions():
+    # Positions are in characters, not bytes
+    js = 'const s = "čšž"; import { a } from "./a.js";'
+    dep = extract_js_dependencies(js)[0]
+    assert js[dep["start"] : dep["end"]] == 'import { a } from "./a.js";'
+    assert js[dep["specifier_start"] : dep["specifier_end"]] == '"./a.js"'
+
+
+def test_invalid_js():
+    with pytest.raises(ValueError, match="Unclosed string"):
+        extract_js_dependencies("const s = 'unclosed")
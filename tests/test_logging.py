@@ -0,0 +1,97 @@
+import logging
+
+import pytest
+
+from djc_core import (
+    cache_clear,
+    cache_info,
+    configure_cache,
+    enable_logging,
+    extract_js_dependencies,
+    scope_css,
+    set_html_attributes,
+)
+
+
+class ListHandler(logging.Handler):
+    def __init__(self):
+        super().__init__(level=1)
+        self.records = []
+
+    def emit(self, record):
+        self.records.append(record)
+
+
+@pytest.fixture(autouse=True)
+def handler():
+    logger = logging.getLogger("djc_core")
+    handler = ListHandler()
+    logger.addHandler(handler)
+    logger.setLevel(1)
+    yield handler
+    enable_logging(False)
+    logger.removeHandler(handler)
+    logger.setLevel(logging.NOTSET)
+
+
+def _messages(handler):
+    return [(record.levelno, record.getMessage()) for record in handler.records]
+
+
+def test_disabled_by_default(handler):
+    set_html_attributes("<div></div>", [], ["data-v"])
+    assert handler.records == []
+
+
+def test_forwards_events(handler):
+    enable_logging()
+    set_html_attributes("<div></div>", [], ["data-v"])
+    scope_css(".a {}", "data-s")
+
+    assert _messages(handler) == [
+        (logging.DEBUG, "transform_html: Transformed HTML output_len=21 captured=0 warnings=0"),
+        (logging.DEBUG, "scope_css: Scoped CSS output_len=13"),
+    ]
+    assert all(record.name == "djc_core" for record in handler.records)
+
+
+def test_errors(handler):
+    enable_logging()
+    with pytest.raises(ValueError):
+        extract_js_dependencies("import 'a")
+
+    [(level, message)] = _messages(handler)
+    assert level == logging.DEBUG
+    assert message.startswith("extract_js_dependencies: Failed to tokenize JS error=Unclosed string")
+
+
+def test_level(handler):
+    enable_logging(level=logging.INFO)
+    set_html_attributes("<div></div>", [], ["data-v"])
+    assert handler.records == []
+
+    enable_logging(level=5)
+    set_html_attributes("<div></div>", [], ["data-v"])
+    assert [level for level, _ in _messages(handler)] == [logging.DEBUG]
+
+    enable_logging(False)
+    set_html_attributes("<div></div>", [], ["data-v"])
+    assert len(handler.records) == 1
+
+
+def test_cache_events_outside_of_lock(handler):
+    # The handler can use the cache, e.g. to log its statistics
+    handler.emit = lambda record: handler.records.append((record.getMessage(), cache_info()["hits"]))
+    configure_cache(16)
+    cache_clear()
+    enable_logging(level=5)
+    try:
+        set_html_attributes("<div></div>", [], ["data-v"])
+        set_html_attributes("<div></div>", [], ["data-v"])
+    finally:
+        configure_cache(0)
+
+    assert [record for record in handler.records if record[0].startswith("Cache")] == [
+        ("Cache miss", 0),
+        ("Cache hit", 1),
+    ]
@@ -0,0 +1,73 @@
+import os
+import subprocess
+import sys
+
+import pytest
+
+from djc_core import (
+    enable_stats,
+    extract_js_dependencies,
+    get_stats,
+    minify_css,
+    reset_stats,
+    set_html_attributes,
+)
+
+
+@pytest.fixture(autouse=True)
+def stats():
+    reset_stats()
+    enable_stats()
+    yield
+    enable_stats(False)
+    reset_stats()
+
+
+def test_records_phases():
+    set_html_attributes("<div></div>", [], ["data-v"])
+    set_html_attributes("<p></p>", [], ["data-v"])
+    extract_js_dependencies('import "a";')
+    minify_css(".a { color: red }")
+
+    stats = get_stats()
+    assert list(stats.keys()) == ["extract_js_dependencies", "minify_css", "set_html_attributes"]
+    assert stats["set_html_attributes"]["calls"] == 2
+    assert sorted(stats["set_html_attributes"]["phases"].keys()) == ["to_python", "transform"]
+    assert sorted(stats["extract_js_dependencies"]["phases"].keys()) == ["parse", "to_python"]
+    assert list(stats["minify_css"]["phases"].keys()) == ["transform"]
+    for function_stats in stats.values():
+        assert all(seconds >= 0 for seconds in function_stats["phases"].values())
+
+
+def test_errors_are_counted():
+    with pytest.raises(ValueError):
+        set_html_attributes("<div></span>", [], [], check_end_names=True)
+
+    stats = get_stats()["set_html_attributes"]
+    assert stats["calls"] == 1
+    assert list(stats["phases"].keys()) == ["transform"]
+
+
+def test_disable_and_reset():
+    enable_stats(False)
+    set_html_attributes("<div></div>", [], [])
+    assert get_stats() == {}
+
+    enable_stats(True)
+    set_html_attributes("<div></div>", [], [])
+    assert get_stats()["set_html_attributes"]["calls"] == 1
+
+    reset_stats()
+    assert get_stats() == {}
+
+
+@pytest.mark.parametrize("value, enabled", [("1", True), ("0", False)])
+def test_env_var(value, enabled):
+    code = (
+        "from djc_core import get_stats, set_html_attributes\n"
+        "set_html_attributes('<div></div>', [], [])\n"
+        "print('set_html_attributes' in get_stats())\n"
+    )
+    env = {**os.environ, "DJC_CORE_STATS": value}
+    output = subprocess.run([sys.executable, "-c", code], env=env, capture_output=True, text=True, check=True)
+    assert output.stdout.strip() == str(enabled)
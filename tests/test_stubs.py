@@ -0,0 +1,69 @@
+import ast
+import inspect
+from pathlib import Path
+from typing import Dict, List
+
+import djc_core
+from djc_core import djc_core as native
+
+# The type stubs are written by hand, so check that they match the extension module
+STUBS_DIR = Path(djc_core.__file__).parent
+
+
+def _stub_modules() -> Dict[str, ast.Module]:
+    init = ast.parse((STUBS_DIR / "__init__.pyi").read_text())
+    modules = {}
+    for node in init.body:
+        assert isinstance(node, ast.ImportFrom), "__init__.pyi should only star-import the stub modules"
+        name = node.module.split(".")[-1]
+        modules[name] = ast.parse((STUBS_DIR / f"{name}.pyi").read_text())
+    return modules
+
+
+def _exported(module: ast.Module) -> List[str]:
+    for node in module.body:
+        if isinstance(node, ast.Assign) and any(getattr(t, "id", None) == "__all__" for t in node.targets):
+            return [elt.value for elt in node.value.elts]
+    raise AssertionError("Stub module has no __all__")
+
+
+def _definitions(module: ast.Module) -> Dict[str, ast.AST]:
+    # Later definitions win, so for overloaded functions it's the implementation
+    return {node.name: node for node in module.body if isinstance(node, (ast.FunctionDef, ast.ClassDef))}
+
+
+def _is_typed_dict(node: ast.AST) -> bool:
+    return isinstance(node, ast.ClassDef) and any(getattr(base, "id", None) == "TypedDict" for base in node.bases)
+
+
+def test_stubs_cover_module():
+    stubbed = set()
+    for name, module in _stub_modules().items():
+        definitions = _definitions(module)
+        for exported in _exported(module):
+            assert exported in definitions, f"{name}.pyi exports undefined '{exported}'"
+            stubbed.add(exported)
+
+    runtime = {name for name in dir(native) if not name.startswith("_")}
+    assert sorted(runtime - stubbed) == [], "Missing in stubs"
+    # Only TypedDicts may exist in stubs alone, as they describe the returned dicts
+    stub_only = {
+        name
+        for module in _stub_modules().values()
+        for name, node in _definitions(module).items()
+        if name in stubbed and name not in runtime and not _is_typed_dict(node)
+    }
+    assert sorted(stub_only) == [], "Missing in the extension module"
+
+
+def test_stub_signatures():
+    for module in _stub_modules().values():
+        for name, node in _definitions(module).items():
+            if not isinstance(node, ast.FunctionDef):
+                continue
+            args = node.args
+            stub_params = [arg.arg for arg in args.posonlyargs + args.args + args.kwonlyargs]
+            runtime_params = inspect.signature(getattr(native, name)).parameters
+            assert stub_params == list(runtime_params), name
+            runtime_kwonly = [p.name for p in runtime_params.values() if p.kind == p.KEYWORD_ONLY]
+            assert [arg.arg for arg in args.kwonlyargs] == runtime_kwonly, name
@@ -0,0 +1,45 @@
+# PyO3 does not support subinterpreters yet (https://github.com/PyO3/pyo3/issues/576).
+# Until then, importing djc_core in a subinterpreter must fail with an ImportError,
+# rather than crash the process.
+
+import sys
+import tempfile
+from pathlib import Path
+
+import pytest
+
+try:
+    import _interpreters as interpreters  # Python 3.13+
+except ImportError:
+    try:
+        import _xxsubinterpreters as interpreters  # type: ignore[no-redef]
+    except ImportError:
+        interpreters = None  # type: ignore[assignment]
+
+
+@pytest.mark.skipif(interpreters is None, reason="Subinterpreters API not available")
+def test_import_in_subinterpreter_raises_import_error():
+    import djc_core  # noqa: F401 - Make sure the module is loaded in the main interpreter first
+
+    with tempfile.TemporaryDirectory() as tmpdir:
+        result_file = Path(tmpdir) / "result.txt"
+        # The script reports the outcome through a file, as the way errors propagate
+        # out of subinterpreters differs between Python versions.
+        script = f"""
+import sys
+sys.path[:] = {sys.path!r}
+try:
+    import djc_core
+    result = "imported"
+except ImportError:
+    result = "ImportError"
+with open({str(result_file)!r}, "w") as f:
+    f.write(result)
+"""
+        interp_id = interpreters.create()
+        try:
+            interpreters.run_string(interp_id, script)
+        finally:
+            interpreters.destroy(interp_id)
+
+        assert result_file.read_text() == "ImportError"